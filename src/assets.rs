@@ -2,11 +2,21 @@ use std::{collections::HashMap, iter::Map};
 
 use asefile::{self, AsepriteFile};
 use image::EncodableLayout;
-use macroquad::prelude::*;
+use macroquad::{
+    miniquad::{BlendFactor, BlendState, BlendValue, Equation},
+    prelude::*,
+};
 
 use crate::{
-    player::{WEAPONS, Weapon},
-    utils::*,
+    audio::{Sfx, TRACKS, Track},
+    background::{BACKDROP_SHEETS, Backdrop},
+    font::PixelFont,
+    interact::{Interactable, Prompt},
+    lighting::{DEFAULT_AMBIENT, Light},
+    modifier::{MODIFIERS, WeaponInstance},
+    pickup::{Locker, LockerItem, Pickup, PickupKind},
+    player::{Player, WEAPONS},
+    terminal::Terminal,
 };
 
 pub struct Assets {
@@ -15,8 +25,14 @@ pub struct Assets {
     pub enemies: AnimationsGroup,
     pub projectiles: AnimationsGroup,
     pub locker: Animation,
-    pub tooltip: Texture2D,
     pub healthbar: Texture2D,
+    pub font: PixelFont,
+    /// Draws textures as a solid silhouette of the draw color, for hit flashes
+    pub flash_material: Material,
+    /// Adds colors together, for drawing lights
+    pub additive_material: Material,
+    /// Multiplies colors, for darkening the scene with the light map
+    pub multiply_material: Material,
 }
 impl Default for Assets {
     fn default() -> Self {
@@ -29,17 +45,93 @@ impl Default for Assets {
             enemies: AnimationsGroup::from_file(include_bytes!("../assets/enemies.ase")),
             projectiles: AnimationsGroup::from_file(include_bytes!("../assets/projectiles.ase")),
             locker: Animation::from_file(include_bytes!("../assets/locker.ase")),
-            tooltip: load_ase_texture(include_bytes!("../assets/tooltip.ase"), None),
             healthbar: load_ase_texture(include_bytes!("../assets/healthbar.ase"), None),
+            font: PixelFont::from_image(
+                &Image::from_file_with_format(
+                    include_bytes!("../assets/font.png"),
+                    Some(ImageFormat::Png),
+                )
+                .unwrap(),
+            ),
+            flash_material: load_material(
+                ShaderSource::Glsl {
+                    vertex: VERTEX_SHADER,
+                    fragment: FLASH_FRAGMENT_SHADER,
+                },
+                MaterialParams::default(),
+            )
+            .unwrap(),
+            additive_material: blend_material(BlendState::new(
+                Equation::Add,
+                BlendFactor::One,
+                BlendFactor::One,
+            )),
+            multiply_material: blend_material(BlendState::new(
+                Equation::Add,
+                BlendFactor::Value(BlendValue::DestinationColor),
+                BlendFactor::Zero,
+            )),
         }
     }
 }
+/// Material with the default shaders and a custom color blend, keeping the target's alpha
+fn blend_material(color_blend: BlendState) -> Material {
+    load_material(
+        ShaderSource::Glsl {
+            vertex: VERTEX_SHADER,
+            fragment: FRAGMENT_SHADER,
+        },
+        MaterialParams {
+            pipeline_params: PipelineParams {
+                color_blend: Some(color_blend),
+                alpha_blend: Some(BlendState::new(
+                    Equation::Add,
+                    BlendFactor::Zero,
+                    BlendFactor::One,
+                )),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .unwrap()
+}
+
+const VERTEX_SHADER: &str = r"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+varying lowp vec2 uv;
+varying lowp vec4 color;
+uniform mat4 Model;
+uniform mat4 Projection;
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+";
+const FLASH_FRAGMENT_SHADER: &str = r"#version 100
+precision lowp float;
+varying vec4 color;
+varying vec2 uv;
+uniform sampler2D Texture;
+void main() {
+    gl_FragColor = vec4(color.rgb, texture2D(Texture, uv).a * color.a);
+}
+";
 
-pub struct StarsBackground {
-    stars: Vec<(Vec2, f32)>,
+const FRAGMENT_SHADER: &str = r"#version 100
+precision lowp float;
+varying vec4 color;
+varying vec2 uv;
+uniform sampler2D Texture;
+void main() {
+    gl_FragColor = color * texture2D(Texture, uv);
 }
+";
+
 pub struct AnimationsGroup {
-    #[expect(dead_code)]
     pub file: AsepriteFile,
     pub animations: Vec<Animation>,
 }
@@ -84,6 +176,10 @@ impl AnimationsGroup {
             animations: tag_frames,
         }
     }
+    pub fn tag_index(&self, name: &str) -> Option<usize> {
+        (0..self.file.num_tags())
+            .position(|i| self.file.get_tag(i).is_some_and(|tag| tag.name() == name))
+    }
 }
 pub struct Animation {
     frames: Vec<(Texture2D, u32)>,
@@ -113,6 +209,12 @@ impl Animation {
             total_length,
         }
     }
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn frame(&self, index: usize) -> &Texture2D {
+        &self.frames[index].0
+    }
     pub fn get_at_time(&self, mut time: u32) -> &Texture2D {
         time %= self.total_length;
         for (texture, length) in self.frames.iter() {
@@ -125,58 +227,34 @@ impl Animation {
         panic!()
     }
 }
-const MAX_STAR_SPEED: f32 = 10.0;
-const MIN_STAR_SPEED: f32 = 5.0;
-impl StarsBackground {
-    pub fn new() -> Self {
-        let star_density = 0.005;
-        let stars_count = (SCREEN_WIDTH * SCREEN_HEIGHT * star_density) as usize;
-        let mut stars: Vec<(Vec2, f32)> = Vec::with_capacity(stars_count);
-        for _ in 0..stars_count {
-            let pos = Vec2::new(
-                rand::gen_range(0, SCREEN_WIDTH as usize) as f32,
-                rand::gen_range(0, SCREEN_HEIGHT as usize) as f32,
-            );
-            stars.push((pos, rand::gen_range(MIN_STAR_SPEED, MAX_STAR_SPEED)));
-        }
-        Self { stars }
-    }
-    pub fn draw(&mut self, delta_time: f32, offset: Vec2) {
-        self.stars.sort_by(|a, b| a.1.total_cmp(&b.1));
-        for (pos, star_speed) in self.stars.iter_mut() {
-            pos.y += delta_time * *star_speed;
-            if pos.y > SCREEN_HEIGHT {
-                *pos = Vec2::new(rand::gen_range(0, SCREEN_WIDTH as usize) as f32, 0.0);
-            }
-            let value = 255
-                - ((MAX_STAR_SPEED - *star_speed) / (MAX_STAR_SPEED - MIN_STAR_SPEED) * 250.0)
-                    as u8;
-            let color = Color::from_rgba(value, value, value, 255);
-            draw_rectangle(
-                pos.x.floor() + offset.x.floor() - SCREEN_WIDTH / 2.0,
-                pos.y.floor() + offset.y.floor() - SCREEN_HEIGHT / 2.0,
-                1.0,
-                1.0,
-                color,
-            );
-        }
-    }
-}
 pub enum TileEntityUpdateResult {
     None,
 }
 type TileEntityDraw = &'static dyn Fn(&mut TileEntity, &Assets, Vec2) -> TileEntityUpdateResult;
+type TileEntityPrompt = &'static dyn Fn(&TileEntity, &Player) -> Option<Prompt>;
+type TileEntityInteract = &'static dyn Fn(&mut TileEntity, &mut Player, &mut Vec<(Sfx, Vec2)>);
 #[derive(Clone, Copy)]
 pub struct TileEntity {
     pub collision: bool,
     pub enabled: bool,
     pub draw: TileEntityDraw,
+    pub prompt: TileEntityPrompt,
+    pub interact: TileEntityInteract,
     pub tile_index: i16,
+    /// Tile the entity stands on
+    pub pos: (i16, i16),
+    /// Glow given off while enabled
+    pub light: Option<Light>,
+    /// Whether the entity stands upright and is y-sorted with characters, instead of lying flat
+    pub tall: bool,
 }
 pub const BARRIER: TileEntity = TileEntity {
     collision: true,
     enabled: true,
     tile_index: 0,
+    pos: (0, 0),
+    light: Some(Light::new(28.0, Color::new(0.25, 0.5, 0.7, 1.0))),
+    tall: true,
     draw: &|this, assets, pos| {
         assets.tileset.draw_tile(
             pos.x,
@@ -187,13 +265,85 @@ pub const BARRIER: TileEntity = TileEntity {
         );
         TileEntityUpdateResult::None
     },
+    prompt: &|_, _| None,
+    interact: &|_, _, _| {},
 };
+/// Door opened and closed by the player, blocking the way while closed
+pub const DOOR: TileEntity = TileEntity {
+    collision: true,
+    enabled: true,
+    tile_index: 0,
+    pos: (0, 0),
+    light: None,
+    tall: true,
+    draw: &|this, assets, pos| {
+        // the open door is the tile after the closed one
+        let tile = this.tile_index + i16::from(!this.enabled);
+        assets
+            .tileset
+            .draw_tile(pos.x, pos.y, (tile % 16) as f32, (tile / 16) as f32, None);
+        TileEntityUpdateResult::None
+    },
+    prompt: &|this, player| {
+        let text = if this.enabled {
+            "Open door"
+        } else if Rect::new(player.pos.x, player.pos.y, 16.0, 16.0).overlaps(&this.rect()) {
+            // closing the door on the player would trap them in it
+            return None;
+        } else {
+            "Close door"
+        };
+        Some(Prompt {
+            text: text.to_string(),
+            pos: this.rect().center(),
+            radius: DOOR_REACH,
+            priority: 0,
+            hold: None,
+        })
+    },
+    interact: &|this, _player, sfx| {
+        this.enabled = !this.enabled;
+        let sound = if this.enabled {
+            Sfx::BarrierClose
+        } else {
+            Sfx::BarrierOpen
+        };
+        sfx.push((sound, this.rect().center()));
+    },
+};
+const DOOR_REACH: f32 = 24.0;
 impl TileEntity {
-    pub fn instantiate(&self, tile_index: i16) -> Self {
+    pub fn instantiate(&self, tile_index: i16, pos: (i16, i16)) -> Self {
         let mut new = *self;
         new.tile_index = tile_index;
+        new.pos = pos;
         new
     }
+    /// Gets the entity placed by a tile in the tile entities layer
+    pub fn from_tile(tile: i16, pos: (i16, i16)) -> Option<Self> {
+        let entity = match tile {
+            81..=83 => BARRIER,
+            84 => DOOR,
+            _ => return None,
+        };
+        Some(entity.instantiate(tile, pos))
+    }
+    fn rect(&self) -> Rect {
+        Rect::new(
+            self.pos.0 as f32 * 16.0,
+            self.pos.1 as f32 * 16.0,
+            16.0,
+            16.0,
+        )
+    }
+}
+impl Interactable for TileEntity {
+    fn prompt(&self, player: &Player) -> Option<Prompt> {
+        (self.prompt)(self, player)
+    }
+    fn interact(&mut self, player: &mut Player, sfx: &mut Vec<(Sfx, Vec2)>) {
+        (self.interact)(self, player, sfx);
+    }
 }
 
 pub struct World {
@@ -203,8 +353,18 @@ pub struct World {
     pub background_details: Vec<Chunk>,
     pub interactable: Vec<Chunk>,
 
-    pub lockers: Vec<(Vec2, Option<&'static Weapon>)>,
+    pub lockers: Vec<Locker>,
+    pub terminals: Vec<Terminal>,
     pub tile_entities: HashMap<(i16, i16), TileEntity>,
+    /// Lamps placed in the interactable layer
+    pub lights: Vec<(Vec2, Light)>,
+    /// Light level where no lights reach
+    pub ambient: Color,
+    /// Areas with their own ambient light, drawn over `ambient`
+    pub rooms: Vec<(Rect, Color)>,
+    pub backdrop: Backdrop,
+    /// Music looped while playing the map
+    pub music: Option<&'static Track>,
 
     pub x_min: i16,
     pub x_max: i16,
@@ -239,7 +399,6 @@ fn generate_successors(pos: (i16, i16), chunks: &[&Chunk]) -> SuccessorIterator
     let mapped: SuccessorIterator = candidates.into_iter().map(map_function);
     mapped
 }
-#[expect(dead_code)]
 impl World {
     pub fn pathfind(&self, from: Vec2, to: Vec2) -> Option<(Vec<(i16, i16)>, i16)> {
         let to = to / 16.0;
@@ -281,34 +440,123 @@ impl World {
         }
         None
     }
-    pub fn set_collision_tile(&mut self, x: i16, y: i16, tile: i16) {
-        let cx = ((x as f32 / 16.0).floor() * 16.0) as i16;
-        let cy = ((y as f32 / 16.0).floor() * 16.0) as i16;
-
-        let chunk = self
-            .collision
-            .iter_mut()
-            .find(|f| f.x == cx && f.y == cy)
-            .unwrap();
-        chunk.tiles[(x - chunk.x + (y - chunk.y) * 16) as usize] = tile;
+    /// Gets all pickups placed in the interactable layer
+    pub fn get_pickup_spawns(&self) -> Vec<Pickup> {
+        let mut pickups = Vec::new();
+        for chunk in self.interactable.iter() {
+            for (i, tile) in chunk.tiles.iter().enumerate() {
+                if let Some(kind) = PickupKind::from_tile(*tile - 1) {
+                    pickups.push(Pickup::new(
+                        kind,
+                        Vec2::new(
+                            (i as i16 % 16 + chunk.x) as f32 * 16.0,
+                            (i as i16 / 16 + chunk.y) as f32 * 16.0,
+                        ),
+                    ));
+                }
+            }
+        }
+        pickups
+    }
+    /// Walks a ray from `from` along `dir` tile by tile until it enters a wall, `max_distance`
+    /// is reached or `hit` returns something for a point along the ray. `dir` must be normalized.
+    pub fn raycast<T>(
+        &self,
+        from: Vec2,
+        dir: Vec2,
+        max_distance: f32,
+        mut hit: impl FnMut(Vec2) -> Option<T>,
+    ) -> (Vec2, Option<T>) {
+        let mut tile = (
+            (from.x / 16.0).floor() as i16,
+            (from.y / 16.0).floor() as i16,
+        );
+        let step = (dir.x.signum() as i16, dir.y.signum() as i16);
+        // distance along the ray between tile edges on each axis
+        let delta = (16.0 / dir).abs();
+        // distance along the ray to the next tile edge on each axis
+        let edge = |tile: i16, from: f32, dir: f32| {
+            if dir == 0.0 {
+                return f32::INFINITY;
+            }
+            let next = if dir > 0.0 { tile + 1 } else { tile };
+            (next as f32 * 16.0 - from) / dir
+        };
+        let mut next = vec2(edge(tile.0, from.x, dir.x), edge(tile.1, from.y, dir.y));
+        let mut chunk: Option<&Chunk> = None;
+        let mut entered = 0.0;
+        let mut sample = 0.0;
+        loop {
+            let (cx, cy) = (tile.0.div_euclid(16) * 16, tile.1.div_euclid(16) * 16);
+            if chunk.is_none_or(|f| (f.x, f.y) != (cx, cy)) {
+                chunk = self.collision.iter().find(|f| (f.x, f.y) == (cx, cy));
+            }
+            let wall = chunk.is_some_and(|f| {
+                f.tile_at((tile.0 - cx) as _, (tile.1 - cy) as _)
+                    .is_some_and(|tile| tile != 0)
+            });
+            if wall
+                || self
+                    .tile_entities
+                    .get(&tile)
+                    .is_some_and(|f| f.collision && f.enabled)
+            {
+                return (from + dir * entered, None);
+            }
+            // points inside this tile, every 2 pixels
+            let exit = next.min_element().min(max_distance);
+            while sample < exit {
+                let pos = from + dir * sample;
+                if let Some(result) = hit(pos) {
+                    return (pos, Some(result));
+                }
+                sample += 2.0;
+            }
+            if exit >= max_distance {
+                return (from + dir * max_distance, None);
+            }
+            entered = exit;
+            if next.x < next.y {
+                tile.0 += step.0;
+                next.x += delta.x;
+            } else {
+                tile.1 += step.1;
+                next.y += delta.y;
+            }
+        }
     }
 }
 impl Default for World {
     fn default() -> Self {
         let xml = include_str!("../assets/station.tmx");
-        let collision = get_layer(xml, "Collision");
+        let collision = get_all_chunks(get_layer(xml, "Collision"));
         let detail = get_layer(xml, "Detail");
         let interactable = get_layer(xml, "Interactable");
         let background = get_layer(xml, "Background");
         let background_details = get_layer(xml, "BackgroundDetails");
         let mut world = World {
-            collision: get_all_chunks(collision),
+            collision,
             details: get_all_chunks(detail),
             background: get_all_chunks(background),
             interactable: get_all_chunks(interactable),
             background_details: get_all_chunks(background_details),
             lockers: Vec::new(),
+            terminals: Vec::new(),
             tile_entities: HashMap::new(),
+            lights: Vec::new(),
+            ambient: get_color(xml, "ambient").unwrap_or(DEFAULT_AMBIENT),
+            rooms: get_rooms(xml),
+            backdrop: Backdrop {
+                seed: get_property(xml, "backdrop_seed")
+                    .and_then(|seed| seed.parse().ok())
+                    .unwrap_or_default(),
+                sheet: get_property(xml, "backdrop_sheet")
+                    .and_then(|name| BACKDROP_SHEETS.iter().find(|f| f.name == name).copied()),
+                nebula_color: get_color(xml, "nebula_color"),
+                planet_color: get_color(xml, "planet_color"),
+            },
+            music: get_property(xml, "music")
+                .and_then(|name| TRACKS.iter().find(|track| track.name == name).copied()),
             x_min: 999,
             y_min: 999,
             y_max: -999,
@@ -342,14 +590,29 @@ impl Default for World {
         for chunk in &world.interactable {
             for (index, tile) in chunk.tiles.iter().enumerate() {
                 let tile = tile - 1;
-                if tile >= 112 && tile <= 127 {
-                    let x = (index % 16) as i16 + chunk.x;
-                    let y = (index / 16) as i16 + chunk.y;
-                    world.lockers.push((
-                        vec2(x as f32 * 16.0, y as f32 * 16.0),
-                        Some(WEAPONS[tile as usize - 112]),
-                    ));
+                let x = (index % 16) as i16 + chunk.x;
+                let y = (index / 16) as i16 + chunk.y;
+                if let Some(light) = Light::from_tile(tile) {
+                    let pos = vec2(x as f32, y as f32) * 16.0 + 8.0;
+                    world.lights.push((pos, light));
+                    continue;
                 }
+                if let Some(terminal) = Terminal::from_tile(tile, vec2(x as f32, y as f32) * 16.0) {
+                    world.terminals.push(terminal);
+                    continue;
+                }
+                let item = match tile {
+                    112..=119 => Some(LockerItem::Weapon(WeaponInstance::new(
+                        WEAPONS[tile as usize - 112],
+                    ))),
+                    120..=127 => Some(LockerItem::Modifier(MODIFIERS[tile as usize - 120])),
+                    _ => continue,
+                };
+                world.lockers.push(Locker {
+                    pos: vec2(x as f32, y as f32) * 16.0,
+                    item,
+                    open: false,
+                });
             }
         }
         for chunk in &tile_entities {
@@ -360,10 +623,8 @@ impl Default for World {
                 }
                 let x = (index % 16) as i16 + chunk.x;
                 let y = (index / 16) as i16 + chunk.y;
-                if (81..=83).contains(&tile) {
-                    world
-                        .tile_entities
-                        .insert((x, y), BARRIER.instantiate(tile));
+                if let Some(entity) = TileEntity::from_tile(tile, (x, y)) {
+                    world.tile_entities.insert((x, y), entity);
                 }
             }
         }
@@ -401,6 +662,47 @@ impl Chunk {
         }
     }
 }
+/// Gets the raw value of a property of the map
+fn get_property<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let properties = xml
+        .split_once("<properties>")?
+        .1
+        .split_once("</properties>")?
+        .0;
+    let property = properties.split_once(&format!("name=\"{name}\""))?.1;
+    Some(property.split_once("value=\"")?.1.split_once('"')?.0)
+}
+/// Gets a color property of the map or an object, stored by Tiled as `#AARRGGBB`
+fn get_color(xml: &str, name: &str) -> Option<Color> {
+    let value = get_property(xml, name)?.strip_prefix('#')?;
+    let argb = u32::from_str_radix(value, 16).ok()?;
+    Some(Color::from_hex(argb & 0xffffff))
+}
+/// Gets the rectangles in the "Rooms" object layer that override the map's ambient light
+fn get_rooms(xml: &str) -> Vec<(Rect, Color)> {
+    let Some((_, group)) = xml.split_once(" name=\"Rooms\">") else {
+        return Vec::new();
+    };
+    let group = group.split_once("</objectgroup>").map_or(group, |f| f.0);
+    let attribute = |tag: &str, name: &str| {
+        tag.split_once(&format!(" {name}=\""))
+            .and_then(|f| f.1.split_once('"'))
+            .and_then(|f| f.0.parse().ok())
+            .unwrap_or(0.0)
+    };
+    (group.split("<object ").skip(1))
+        .filter_map(|object| {
+            let tag = object.split_once('>')?.0;
+            let rect = Rect::new(
+                attribute(tag, "x"),
+                attribute(tag, "y"),
+                attribute(tag, "width"),
+                attribute(tag, "height"),
+            );
+            Some((rect, get_color(object, "ambient")?))
+        })
+        .collect()
+}
 fn get_all_chunks(xml: &str) -> Vec<Chunk> {
     let mut chunks = Vec::new();
     let mut xml = xml.to_string();
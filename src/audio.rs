@@ -0,0 +1,512 @@
+use macroquad::{
+    audio::{
+        PlaySoundParams, Sound, load_sound_from_bytes, play_sound, set_sound_volume, stop_sound,
+    },
+    prelude::*,
+    rand::RandGenerator,
+};
+
+use crate::settings::Settings;
+
+/// Rate sounds are synthesized at, quad-snd resamples them for the device
+const SAMPLE_RATE: f32 = 22050.0;
+/// Positions each effect is rendered at from left to right, since playing sounds can't be panned
+const PAN_STEPS: usize = 5;
+/// Distance from the player, in pixels, at which effects can no longer be heard
+const HEARING_DISTANCE: f32 = 320.0;
+/// Horizontal distance from the player at which effects are fully to one side
+const PAN_DISTANCE: f32 = 200.0;
+const CROSSFADE_TIME: f32 = 1.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Wave {
+    Sine,
+    Square,
+    Saw,
+    /// Random values, held for a cycle so the pitch colors the noise
+    Noise,
+}
+
+/// A wave sliding between two pitches while it fades out
+pub struct Tone {
+    pub wave: Wave,
+    /// Start and end frequency, in hz
+    pub pitch: (f32, f32),
+    /// Seconds into the effect the tone starts at
+    pub delay: f32,
+    pub duration: f32,
+    pub volume: f32,
+}
+impl Tone {
+    const fn new(wave: Wave, pitch: (f32, f32), duration: f32, volume: f32) -> Self {
+        Self {
+            wave,
+            pitch,
+            delay: 0.0,
+            duration,
+            volume,
+        }
+    }
+    const fn delayed(self, delay: f32) -> Self {
+        Self { delay, ..self }
+    }
+    /// Samples of the tone on its own, without its delay
+    fn render(&self, rng: &RandGenerator) -> Vec<f32> {
+        let count = (self.duration * SAMPLE_RATE) as usize;
+        let mut phase: f32 = 0.0;
+        let mut noise = 0.0;
+        (0..count)
+            .map(|i| {
+                let t = i as f32 / count as f32;
+                let pitch = self.pitch.0 + (self.pitch.1 - self.pitch.0) * t;
+                let last_phase = phase;
+                phase += pitch / SAMPLE_RATE;
+                let value = match self.wave {
+                    Wave::Sine => (phase * std::f32::consts::TAU).sin(),
+                    Wave::Square => 1.0 - (phase.fract() * 2.0).floor() * 2.0,
+                    Wave::Saw => phase.fract() * 2.0 - 1.0,
+                    Wave::Noise => {
+                        if phase.floor() != last_phase.floor() {
+                            noise = rng.gen_range(-1.0, 1.0);
+                        }
+                        noise
+                    }
+                };
+                // a short attack keeps tones from clicking
+                let envelope = (i as f32 / 64.0).min(1.0) * (1.0 - t);
+                value * envelope * self.volume
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Sfx {
+    Gun,
+    Rifle,
+    Shotgun,
+    Laser,
+    Cannon,
+    Swing,
+    Hit,
+    Emerge,
+    LockerOpen,
+    BarrierOpen,
+    BarrierClose,
+    PlayerHurt,
+}
+static SFX: &[Sfx] = &[
+    Sfx::Gun,
+    Sfx::Rifle,
+    Sfx::Shotgun,
+    Sfx::Laser,
+    Sfx::Cannon,
+    Sfx::Swing,
+    Sfx::Hit,
+    Sfx::Emerge,
+    Sfx::LockerOpen,
+    Sfx::BarrierOpen,
+    Sfx::BarrierClose,
+    Sfx::PlayerHurt,
+];
+static GUN_TONES: &[Tone] = &[
+    Tone::new(Wave::Square, (880.0, 220.0), 0.18, 0.3),
+    Tone::new(Wave::Sine, (440.0, 110.0), 0.2, 0.3),
+];
+static RIFLE_TONES: &[Tone] = &[
+    Tone::new(Wave::Square, (1200.0, 400.0), 0.08, 0.25),
+    Tone::new(Wave::Noise, (6000.0, 2000.0), 0.05, 0.15),
+];
+static SHOTGUN_TONES: &[Tone] = &[
+    Tone::new(Wave::Noise, (3000.0, 400.0), 0.3, 0.5),
+    Tone::new(Wave::Square, (300.0, 60.0), 0.2, 0.3),
+];
+static LASER_TONES: &[Tone] = &[Tone::new(Wave::Saw, (1500.0, 1400.0), 0.1, 0.12)];
+static CANNON_TONES: &[Tone] = &[
+    Tone::new(Wave::Square, (440.0, 60.0), 0.3, 0.35),
+    Tone::new(Wave::Noise, (2000.0, 200.0), 0.25, 0.3),
+];
+static SWING_TONES: &[Tone] = &[Tone::new(Wave::Noise, (600.0, 2400.0), 0.14, 0.25)];
+static HIT_TONES: &[Tone] = &[
+    Tone::new(Wave::Square, (220.0, 80.0), 0.07, 0.3),
+    Tone::new(Wave::Noise, (4000.0, 1000.0), 0.05, 0.2),
+];
+static EMERGE_TONES: &[Tone] = &[
+    Tone::new(Wave::Noise, (300.0, 80.0), 0.6, 0.4),
+    Tone::new(Wave::Sine, (90.0, 50.0), 0.6, 0.5),
+];
+static LOCKER_OPEN_TONES: &[Tone] = &[
+    Tone::new(Wave::Noise, (3000.0, 3000.0), 0.15, 0.1),
+    Tone::new(Wave::Square, (330.0, 330.0), 0.08, 0.15),
+    Tone::new(Wave::Square, (494.0, 494.0), 0.12, 0.15).delayed(0.07),
+];
+static BARRIER_OPEN_TONES: &[Tone] = &[Tone::new(Wave::Saw, (200.0, 600.0), 0.35, 0.25)];
+static BARRIER_CLOSE_TONES: &[Tone] = &[Tone::new(Wave::Saw, (600.0, 200.0), 0.3, 0.25)];
+static PLAYER_HURT_TONES: &[Tone] = &[
+    Tone::new(Wave::Square, (400.0, 150.0), 0.2, 0.35),
+    Tone::new(Wave::Noise, (1500.0, 500.0), 0.15, 0.3),
+];
+impl Sfx {
+    fn tones(&self) -> &'static [Tone] {
+        match self {
+            Sfx::Gun => GUN_TONES,
+            Sfx::Rifle => RIFLE_TONES,
+            Sfx::Shotgun => SHOTGUN_TONES,
+            Sfx::Laser => LASER_TONES,
+            Sfx::Cannon => CANNON_TONES,
+            Sfx::Swing => SWING_TONES,
+            Sfx::Hit => HIT_TONES,
+            Sfx::Emerge => EMERGE_TONES,
+            Sfx::LockerOpen => LOCKER_OPEN_TONES,
+            Sfx::BarrierOpen => BARRIER_OPEN_TONES,
+            Sfx::BarrierClose => BARRIER_CLOSE_TONES,
+            Sfx::PlayerHurt => PLAYER_HURT_TONES,
+        }
+    }
+    /// Mono samples of the effect
+    fn render(&self, rng: &RandGenerator) -> Vec<f32> {
+        let tones = self.tones();
+        let length = (tones.iter())
+            .map(|tone| tone.delay + tone.duration)
+            .fold(0.0, f32::max);
+        let mut samples = vec![0.0; (length * SAMPLE_RATE) as usize + 1];
+        for tone in tones {
+            let start = (tone.delay * SAMPLE_RATE) as usize;
+            for (i, sample) in tone.render(rng).into_iter().enumerate() {
+                samples[start + i] += sample;
+            }
+        }
+        samples
+    }
+}
+
+/// Layer of a track. Every stem of a track loops together, faded in as combat gets more intense.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Stem {
+    /// Bass line and arpeggio, always playing
+    Base,
+    /// Kick and hi-hat
+    Percussion,
+    /// Snare and octave bass stabs
+    Combat,
+}
+static STEMS: &[Stem] = &[Stem::Base, Stem::Percussion, Stem::Combat];
+impl Stem {
+    /// Volume of the stem at a combat intensity from 0 to 1
+    fn volume(&self, intensity: f32) -> f32 {
+        let (start, end) = match self {
+            Stem::Base => return 1.0,
+            Stem::Percussion => (0.0, 0.4),
+            Stem::Combat => (0.4, 0.9),
+        };
+        ((intensity - start) / (end - start)).clamp(0.0, 1.0)
+    }
+}
+
+/// Looping music, an arpeggio over a bass line, split into stems
+pub struct Track {
+    /// Name maps refer to the track by
+    pub name: &'static str,
+    /// Beats per minute
+    pub tempo: f32,
+    /// Bass note of each bar, as a midi note number
+    pub roots: &'static [u8],
+    /// Eighth notes played over each bar, in semitones above the root's next octave
+    pub arpeggio: &'static [u8],
+    pub bass: Wave,
+    pub lead: Wave,
+}
+pub static TITLE_MUSIC: Track = Track {
+    name: "title",
+    tempo: 80.0,
+    roots: &[45, 41, 48, 43],
+    arpeggio: &[0, 7, 12, 7],
+    bass: Wave::Sine,
+    lead: Wave::Sine,
+};
+pub static STATION_MUSIC: Track = Track {
+    name: "station",
+    tempo: 112.0,
+    roots: &[40, 40, 43, 38],
+    arpeggio: &[0, 12, 7, 12, 3, 12, 7, 10],
+    bass: Wave::Saw,
+    lead: Wave::Square,
+};
+/// Played while a boss is alive, in place of the map's music
+pub static BOSS_MUSIC: Track = Track {
+    name: "boss",
+    tempo: 140.0,
+    roots: &[38, 38, 39, 36],
+    arpeggio: &[0, 3, 7, 12, 13, 12, 7, 3],
+    bass: Wave::Square,
+    lead: Wave::Saw,
+};
+pub static TRACKS: &[&Track] = &[&TITLE_MUSIC, &STATION_MUSIC, &BOSS_MUSIC];
+impl Track {
+    /// Stereo frames of one loop of each stem, in the order of `STEMS`
+    fn render(&self, rng: &RandGenerator) -> Vec<Vec<(f32, f32)>> {
+        let beat = (60.0 / self.tempo * SAMPLE_RATE) as usize;
+        let mut stems = vec![vec![(0.0, 0.0); beat * 4 * self.roots.len()]; STEMS.len()];
+        let mut add = |stem: Stem, start: usize, tone: Tone, pan: f32| {
+            let (left, right) = pan_gains(pan);
+            let frames = &mut stems[STEMS.iter().position(|f| *f == stem).unwrap()];
+            for (frame, sample) in frames[start..].iter_mut().zip(tone.render(rng)) {
+                frame.0 += sample * left;
+                frame.1 += sample * right;
+            }
+        };
+        let seconds = |samples: usize| samples as f32 / SAMPLE_RATE;
+        for (bar, root) in self.roots.iter().enumerate() {
+            let start = bar * beat * 4;
+            let bass = midi_to_hz(*root);
+            for i in 0..4 {
+                let beat_start = start + i * beat;
+                let tone = Tone::new(self.bass, (bass, bass), seconds(beat), 0.3);
+                add(Stem::Base, beat_start, tone, 0.0);
+
+                let kick = Tone::new(Wave::Sine, (150.0, 40.0), seconds(beat / 3), 0.5);
+                add(Stem::Percussion, beat_start, kick, 0.0);
+                let hat = Tone::new(Wave::Noise, (8000.0, 8000.0), seconds(beat / 4), 0.05);
+                add(Stem::Percussion, beat_start + beat / 2, hat, 0.5);
+
+                // snare on the second and fourth beat
+                if i % 2 == 1 {
+                    let snare = Tone::new(Wave::Noise, (3000.0, 1500.0), seconds(beat / 3), 0.25);
+                    add(Stem::Combat, beat_start, snare, -0.2);
+                }
+            }
+            for i in 0..8 {
+                let note = midi_to_hz(root + 12 + self.arpeggio[i % self.arpeggio.len()]);
+                let tone = Tone::new(self.lead, (note, note), seconds(beat / 2), 0.1);
+                // alternate sides, so the arpeggio moves across the stereo field
+                let pan = if i % 2 == 0 { -0.3 } else { 0.3 };
+                add(Stem::Base, start + i * beat / 2, tone, pan);
+
+                let stab = Tone::new(
+                    Wave::Square,
+                    (bass * 2.0, bass * 2.0),
+                    seconds(beat / 4),
+                    0.1,
+                );
+                add(Stem::Combat, start + i * beat / 2, stab, 0.2);
+            }
+        }
+        stems
+    }
+}
+
+fn midi_to_hz(note: u8) -> f32 {
+    440.0 * 2.0_f32.powf((note as f32 - 69.0) / 12.0)
+}
+/// Left and right volume for a pan from -1 (left) to 1 (right), keeping the center at full volume
+fn pan_gains(pan: f32) -> (f32, f32) {
+    ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0))
+}
+/// Encodes stereo frames as a 16 bit wav file, a format quad-snd can load
+fn encode_wav(frames: &[(f32, f32)]) -> Vec<u8> {
+    let data_size = frames.len() as u32 * 4;
+    let mut bytes = Vec::with_capacity(44 + data_size as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16_u32.to_le_bytes());
+    // uncompressed, two channels
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&2_u16.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE as u32).to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE as u32 * 4).to_le_bytes());
+    bytes.extend_from_slice(&4_u16.to_le_bytes());
+    bytes.extend_from_slice(&16_u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_size.to_le_bytes());
+    for (left, right) in frames {
+        for sample in [left, right] {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+    }
+    bytes
+}
+/// Whether there's a device to play sound on, tried the same way quad-snd opens one.
+/// quad-snd's audio thread gives up without one, after which every sound played logs an error.
+#[cfg(all(feature = "audio", target_os = "linux"))]
+fn has_output_device() -> bool {
+    use quad_alsa_sys as alsa;
+
+    [c"default", c"pipewire"].iter().any(|device| {
+        let mut pcm = std::ptr::null_mut();
+        // SAFETY: the device name is nul terminated and the handle is closed once opened
+        unsafe {
+            let opened = alsa::snd_pcm_open(
+                &mut pcm,
+                device.as_ptr(),
+                alsa::SND_PCM_STREAM_PLAYBACK,
+                alsa::SND_PCM_NONBLOCK,
+            ) >= 0;
+            if opened {
+                alsa::snd_pcm_close(pcm);
+            }
+            opened
+        }
+    })
+}
+#[cfg(not(all(feature = "audio", target_os = "linux")))]
+fn has_output_device() -> bool {
+    cfg!(feature = "audio")
+}
+
+struct Sounds {
+    /// Each effect at every pan step, in the order of `SFX`
+    sfx: Vec<Vec<Sound>>,
+    /// Stems of each track, in the order of `TRACKS` and `STEMS`
+    music: Vec<Vec<Sound>>,
+}
+
+async fn load_sounds() -> Result<Sounds, macroquad::Error> {
+    let rng = RandGenerator::new();
+    rng.srand(1);
+    let mut sfx = Vec::new();
+    for effect in SFX {
+        let samples = effect.render(&rng);
+        let mut steps = Vec::new();
+        for step in 0..PAN_STEPS {
+            let (left, right) = pan_gains(step as f32 / (PAN_STEPS - 1) as f32 * 2.0 - 1.0);
+            let frames: Vec<_> = (samples.iter())
+                .map(|sample| (sample * left, sample * right))
+                .collect();
+            steps.push(load_sound_from_bytes(&encode_wav(&frames)).await?);
+        }
+        sfx.push(steps);
+    }
+    let mut music = Vec::new();
+    for track in TRACKS {
+        let mut stems = Vec::new();
+        for frames in track.render(&rng) {
+            stems.push(load_sound_from_bytes(&encode_wav(&frames)).await?);
+        }
+        music.push(stems);
+    }
+    Ok(Sounds { sfx, music })
+}
+
+/// Plays sound effects around the player and crossfades between music tracks.
+/// Stays silent without an audio device or the `audio` feature.
+pub struct Audio {
+    sounds: Option<Sounds>,
+    current: Option<&'static Track>,
+    /// Tracks playing and their volume, the current one fading in and the others out
+    playing: Vec<(&'static Track, f32)>,
+}
+impl Audio {
+    /// Synthesizes every sound once there's a device to hear them on, staying silent otherwise
+    pub async fn load() -> Self {
+        let sounds = if has_output_device() {
+            match load_sounds().await {
+                Ok(sounds) => Some(sounds),
+                Err(err) => {
+                    eprintln!("failed to load sounds, sound is disabled: {err}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        Self {
+            sounds,
+            current: None,
+            playing: Vec::new(),
+        }
+    }
+    /// Plays effects made at the given positions, quieter and to the side the further they are from `listener`.
+    /// Only the loudest of each effect is played, so effects made together don't stack up.
+    pub fn play_sfx(
+        &self,
+        sfx: impl Iterator<Item = (Sfx, Vec2)>,
+        listener: Vec2,
+        settings: &Settings,
+    ) {
+        let Some(sounds) = &self.sounds else {
+            return;
+        };
+        let mut loudest: Vec<Option<(f32, usize)>> = vec![None; SFX.len()];
+        for (effect, pos) in sfx {
+            let delta = pos - listener;
+            let volume = (1.0 - delta.length() / HEARING_DISTANCE).max(0.0);
+            let pan = (delta.x / PAN_DISTANCE).clamp(-1.0, 1.0);
+            let step = ((pan + 1.0) / 2.0 * (PAN_STEPS - 1) as f32).round() as usize;
+            let slot = &mut loudest[effect as usize];
+            if volume > 0.0 && slot.is_none_or(|(loudest, _)| volume > loudest) {
+                *slot = Some((volume, step));
+            }
+        }
+        for (effect, loudest) in loudest.into_iter().enumerate() {
+            if let Some((volume, step)) = loudest {
+                play_sound(
+                    &sounds.sfx[effect][step],
+                    PlaySoundParams {
+                        looped: false,
+                        volume: volume * settings.sfx_volume * settings.master_volume,
+                    },
+                );
+            }
+        }
+    }
+    /// Crossfades to `track`, or to silence, with its stems following the combat intensity
+    pub fn update_music(
+        &mut self,
+        delta_time: f32,
+        track: Option<&'static Track>,
+        intensity: f32,
+        settings: &Settings,
+    ) {
+        let Some(sounds) = &self.sounds else {
+            return;
+        };
+        let stems = |track: &Track| {
+            let index = TRACKS.iter().position(|f| f.name == track.name).unwrap();
+            &sounds.music[index]
+        };
+        let same = |a: Option<&Track>, b: Option<&Track>| a.map(|f| f.name) == b.map(|f| f.name);
+        if !same(track, self.current) {
+            self.current = track;
+            if let Some(track) = track
+                && !(self.playing.iter()).any(|(playing, _)| playing.name == track.name)
+            {
+                // started together, so the stems stay in sync
+                for stem in stems(track) {
+                    play_sound(
+                        stem,
+                        PlaySoundParams {
+                            looped: true,
+                            volume: 0.0,
+                        },
+                    );
+                }
+                self.playing.push((track, 0.0));
+            }
+        }
+        let step = delta_time / CROSSFADE_TIME;
+        let current = self.current;
+        self.playing.retain_mut(|(track, volume)| {
+            if same(Some(track), current) {
+                *volume = (*volume + step).min(1.0);
+            } else {
+                *volume -= step;
+                if *volume <= 0.0 {
+                    stems(track).iter().for_each(stop_sound);
+                    return false;
+                }
+            }
+            for (stem, sound) in STEMS.iter().zip(stems(track)) {
+                set_sound_volume(
+                    sound,
+                    *volume
+                        * stem.volume(intensity)
+                        * settings.music_volume
+                        * settings.master_volume,
+                );
+            }
+            true
+        });
+    }
+}
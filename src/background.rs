@@ -0,0 +1,217 @@
+use std::f32::consts::PI;
+
+use macroquad::{prelude::*, rand::RandGenerator};
+
+use crate::{assets::AnimationsGroup, utils::*};
+
+/// Stars on a layer wrap around a tile of this size, so every layer fills the screen
+const STAR_TILE: Vec2 = vec2(SCREEN_WIDTH, SCREEN_HEIGHT);
+/// Seconds between shooting stars
+const SHOOTING_STAR_INTERVAL: (f32, f32) = (4.0, 10.0);
+const SHOOTING_STAR_SPEED: f32 = 260.0;
+const SHOOTING_STAR_LIFETIME: f32 = 0.5;
+const SHOOTING_STAR_LENGTH: f32 = 14.0;
+
+struct StarLayer {
+    /// How far the layer moves relative to the camera
+    parallax: f32,
+    /// Stars per pixel
+    density: f32,
+    brightness: f32,
+}
+static STAR_LAYERS: &[StarLayer] = &[
+    StarLayer {
+        parallax: 0.04,
+        density: 0.003,
+        brightness: 0.35,
+    },
+    StarLayer {
+        parallax: 0.1,
+        density: 0.0015,
+        brightness: 0.6,
+    },
+    StarLayer {
+        parallax: 0.2,
+        density: 0.0006,
+        brightness: 1.0,
+    },
+];
+const NEBULA_PARALLAX: f32 = 0.02;
+const PLANET_PARALLAX: f32 = 0.06;
+
+/// Aseprite file with nebula and planet sprites, one per frame under the `nebula` and `planet` tags.
+/// Sprites are drawn white so maps can tint them.
+pub struct BackdropSheet {
+    /// Name maps refer to the sheet by
+    pub name: &'static str,
+    bytes: &'static [u8],
+}
+pub static STATION_BACKDROP: BackdropSheet = BackdropSheet {
+    name: "station",
+    bytes: include_bytes!("../assets/backdrops.ase"),
+};
+pub static BACKDROP_SHEETS: &[&BackdropSheet] = &[&STATION_BACKDROP];
+
+/// Backdrop settings of a map, read from its properties
+#[derive(Default)]
+pub struct Backdrop {
+    /// Seed every star, nebula and planet position is generated from
+    pub seed: u64,
+    /// Sheet the nebula and planet sprites are picked from
+    pub sheet: Option<&'static BackdropSheet>,
+    pub nebula_color: Option<Color>,
+    pub planet_color: Option<Color>,
+}
+
+struct Star {
+    layer: usize,
+    pos: Vec2,
+    twinkle_speed: f32,
+    twinkle_phase: f32,
+}
+
+struct ShootingStar {
+    pos: Vec2,
+    dir: Vec2,
+    time: f32,
+}
+
+/// Sprite drawn behind the stars, moving with its own parallax
+struct Prop {
+    texture: Texture2D,
+    color: Color,
+    pos: Vec2,
+    parallax: f32,
+}
+
+/// Layers of stars, nebulae and planets drawn behind the world with parallax
+pub struct Background {
+    stars: Vec<Star>,
+    props: Vec<Prop>,
+    shooting_star: Option<ShootingStar>,
+    next_shooting_star: f32,
+    /// Point the parallax is relative to, so props sit around it
+    center: Vec2,
+    time: f32,
+}
+impl Background {
+    pub fn new(backdrop: &Backdrop, center: Vec2) -> Self {
+        let rng = RandGenerator::new();
+        rng.srand(backdrop.seed);
+
+        let mut stars = Vec::new();
+        for (layer, star_layer) in STAR_LAYERS.iter().enumerate() {
+            let count = (STAR_TILE.x * STAR_TILE.y * star_layer.density) as usize;
+            for _ in 0..count {
+                stars.push(Star {
+                    layer,
+                    pos: vec2(
+                        rng.gen_range(0.0, STAR_TILE.x),
+                        rng.gen_range(0.0, STAR_TILE.y),
+                    ),
+                    twinkle_speed: rng.gen_range(0.5, 3.0),
+                    twinkle_phase: rng.gen_range(0.0, PI * 2.0),
+                });
+            }
+        }
+
+        let mut props = Vec::new();
+        let sprites = backdrop
+            .sheet
+            .map(|sheet| AnimationsGroup::from_file(sheet.bytes));
+        let tag = |name: &str| {
+            let sprites = sprites.as_ref()?;
+            Some(&sprites.animations[sprites.tag_index(name)?])
+        };
+        if let Some(nebulae) = tag("nebula") {
+            for _ in 0..rng.gen_range(2, 4) {
+                let pos = vec2(
+                    rng.gen_range(-SCREEN_WIDTH, SCREEN_WIDTH),
+                    rng.gen_range(-SCREEN_HEIGHT, SCREEN_HEIGHT),
+                ) / 2.0;
+                props.push(Prop {
+                    texture: nebulae
+                        .frame(rng.gen_range(0, nebulae.frame_count()))
+                        .clone(),
+                    color: backdrop.nebula_color.unwrap_or(WHITE),
+                    pos,
+                    parallax: NEBULA_PARALLAX,
+                });
+            }
+        }
+        if let Some(planets) = tag("planet") {
+            let pos = vec2(
+                rng.gen_range(-SCREEN_WIDTH, SCREEN_WIDTH),
+                rng.gen_range(-SCREEN_HEIGHT, SCREEN_HEIGHT),
+            ) / 3.0;
+            props.push(Prop {
+                texture: planets
+                    .frame(rng.gen_range(0, planets.frame_count()))
+                    .clone(),
+                color: backdrop.planet_color.unwrap_or(WHITE),
+                pos,
+                parallax: PLANET_PARALLAX,
+            });
+        }
+
+        Self {
+            stars,
+            props,
+            shooting_star: None,
+            next_shooting_star: rng.gen_range(SHOOTING_STAR_INTERVAL.0, SHOOTING_STAR_INTERVAL.1),
+            center,
+            time: 0.0,
+        }
+    }
+    /// Draws the backdrop for a view centered on `camera_pos`
+    pub fn draw(&mut self, delta_time: f32, camera_pos: Vec2) {
+        self.time += delta_time;
+        let view_origin = camera_pos.floor() - STAR_TILE / 2.0;
+        let offset = camera_pos - self.center;
+
+        for prop in &self.props {
+            let size = prop.texture.size();
+            let pos = camera_pos + prop.pos - offset * prop.parallax - size / 2.0;
+            draw_texture(&prop.texture, pos.x.floor(), pos.y.floor(), prop.color);
+        }
+
+        for star in &self.stars {
+            let layer = &STAR_LAYERS[star.layer];
+            let pos = (star.pos - offset * layer.parallax).rem_euclid(STAR_TILE);
+            let twinkle = (self.time * star.twinkle_speed + star.twinkle_phase).sin() * 0.5 + 0.5;
+            let value = layer.brightness * (0.6 + 0.4 * twinkle);
+            draw_rectangle(
+                view_origin.x + pos.x.floor(),
+                view_origin.y + pos.y.floor(),
+                1.0,
+                1.0,
+                Color::new(value, value, value, 1.0),
+            );
+        }
+
+        self.next_shooting_star -= delta_time;
+        if self.next_shooting_star <= 0.0 {
+            self.next_shooting_star =
+                rand::gen_range(SHOOTING_STAR_INTERVAL.0, SHOOTING_STAR_INTERVAL.1);
+            self.shooting_star = Some(ShootingStar {
+                pos: vec2(
+                    rand::gen_range(0.0, STAR_TILE.x),
+                    rand::gen_range(0.0, STAR_TILE.y / 2.0),
+                ),
+                dir: Vec2::from_angle(rand::gen_range(PI * 0.6, PI * 0.85)),
+                time: 0.0,
+            });
+        }
+        if let Some(shooting_star) = &mut self.shooting_star {
+            shooting_star.time += delta_time;
+            shooting_star.pos += shooting_star.dir * SHOOTING_STAR_SPEED * delta_time;
+            let alpha = 1.0 - shooting_star.time / SHOOTING_STAR_LIFETIME;
+            let head = view_origin + shooting_star.pos.floor();
+            let tail = head - shooting_star.dir * SHOOTING_STAR_LENGTH;
+            draw_line(head.x, head.y, tail.x, tail.y, 1.0, WHITE.with_alpha(alpha));
+            if alpha <= 0.0 {
+                self.shooting_star = None;
+            }
+        }
+    }
+}
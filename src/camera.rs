@@ -0,0 +1,102 @@
+use macroquad::prelude::*;
+
+use crate::{assets::World, utils::*};
+
+pub struct CameraSettings {
+    /// How quickly the camera catches up, higher is snappier
+    pub smoothing: f32,
+    /// Fraction of the cursor's offset from the view center the camera leans towards
+    pub look_ahead: f32,
+    pub max_look_ahead: f32,
+    /// Half size of the area the focus can move in without the camera following
+    pub dead_zone: Vec2,
+    /// Offset at full trauma
+    pub max_shake: f32,
+    /// Trauma lost per second
+    pub shake_decay: f32,
+    /// How quickly recoil kicks return to zero
+    pub kick_recovery: f32,
+}
+pub static CAMERA: CameraSettings = CameraSettings {
+    smoothing: 8.0,
+    look_ahead: 0.2,
+    max_look_ahead: 40.0,
+    dead_zone: vec2(6.0, 4.0),
+    max_shake: 6.0,
+    shake_decay: 1.5,
+    kick_recovery: 15.0,
+};
+
+/// Camera following the player, kept inside the room they're in or else the map
+pub struct GameCamera {
+    pub settings: &'static CameraSettings,
+    /// Smoothed center of the view, without kick or shake
+    pub pos: Vec2,
+    pub kick: Vec2,
+    /// Between 0 and 1, shake strength is this squared
+    trauma: f32,
+    shake_offset: Vec2,
+}
+impl GameCamera {
+    pub fn new(pos: Vec2) -> Self {
+        Self {
+            settings: &CAMERA,
+            pos,
+            kick: Vec2::ZERO,
+            trauma: 0.0,
+            shake_offset: Vec2::ZERO,
+        }
+    }
+    /// Shakes the screen. Amounts add up, with 1 being the strongest shake.
+    pub fn shake(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+    pub fn target(&self) -> Vec2 {
+        self.pos + self.kick + self.shake_offset
+    }
+    /// Moves towards `focus`, leaning by `look` which is the crosshair's offset from the view center
+    pub fn update(&mut self, delta_time: f32, focus: Vec2, look: Vec2, world: &World) {
+        let settings = self.settings;
+        let map = Rect::new(
+            world.x_min as f32 * 16.0,
+            world.y_min as f32 * 16.0,
+            (world.x_max - world.x_min + 16) as f32 * 16.0,
+            (world.y_max - world.y_min + 16) as f32 * 16.0,
+        );
+        // the room is only aimed for, so moving between rooms glides instead of snapping
+        let room = (world.rooms.iter())
+            .map(|(rect, _)| *rect)
+            .find(|rect| rect.contains(focus));
+        let desired =
+            focus + (look * settings.look_ahead).clamp_length_max(settings.max_look_ahead);
+        let desired = clamp_view(desired, room.unwrap_or(map));
+        let delta = desired - self.pos;
+        let outside = delta - delta.clamp(-settings.dead_zone, settings.dead_zone);
+        self.pos += outside * (1.0 - (-settings.smoothing * delta_time).exp());
+        self.pos = clamp_view(self.pos, map);
+
+        self.kick = self
+            .kick
+            .lerp(Vec2::ZERO, settings.kick_recovery * delta_time);
+        self.trauma = (self.trauma - settings.shake_decay * delta_time).max(0.0);
+        let strength = self.trauma * self.trauma * settings.max_shake;
+        self.shake_offset = vec2(rand::gen_range(-1.0, 1.0), rand::gen_range(-1.0, 1.0)) * strength;
+    }
+}
+/// Keeps a view centered at `pos` inside `bounds`, centering it on axes where they're smaller than the screen
+fn clamp_view(pos: Vec2, bounds: Rect) -> Vec2 {
+    let half_screen = vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+    let min = bounds.point() + half_screen;
+    let max = bounds.point() + bounds.size() - half_screen;
+    vec2(
+        clamp_or_center(pos.x, min.x, max.x),
+        clamp_or_center(pos.y, min.y, max.y),
+    )
+}
+fn clamp_or_center(value: f32, min: f32, max: f32) -> f32 {
+    if min > max {
+        (min + max) / 2.0
+    } else {
+        value.clamp(min, max)
+    }
+}
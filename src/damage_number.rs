@@ -0,0 +1,40 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::Assets,
+    font::{Align, TextStyle, draw_pixel_text},
+};
+
+const DAMAGE_NUMBER_TIME: f32 = 0.6;
+const DAMAGE_NUMBER_COLOR: Color = Color::from_hex(0xf2d95c);
+
+/// Numbers floating up from damaged enemies
+pub struct DamageNumbers {
+    numbers: Vec<(u32, Vec2, f32)>,
+}
+impl DamageNumbers {
+    pub fn new() -> Self {
+        Self {
+            numbers: Vec::new(),
+        }
+    }
+    pub fn spawn(&mut self, amount: u32, pos: Vec2) {
+        let offset = vec2(rand::gen_range(-4.0, 4.0), 0.0);
+        self.numbers.push((amount, pos + offset, 0.0));
+    }
+    pub fn update(&mut self, delta_time: f32, assets: &Assets) {
+        self.numbers.retain_mut(|(amount, pos, time)| {
+            *time += delta_time;
+            pos.y -= 20.0 * delta_time * (1.0 - *time / DAMAGE_NUMBER_TIME);
+            let alpha = (1.0 - *time / DAMAGE_NUMBER_TIME).min(1.0);
+            let style = TextStyle {
+                color: DAMAGE_NUMBER_COLOR.with_alpha(alpha),
+                align: Align::Center,
+                outline: Some(BLACK.with_alpha(alpha)),
+                ..Default::default()
+            };
+            draw_pixel_text(&assets.font, &amount.to_string(), pos.x, pos.y, &style);
+            *time < DAMAGE_NUMBER_TIME
+        });
+    }
+}
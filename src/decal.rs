@@ -0,0 +1,165 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::utils::create_camera;
+
+/// Decals past this amount start fading out, oldest first
+const MAX_DECALS: usize = 48;
+/// Decals older than this start fading out
+const DECAL_LIFETIME: f32 = 90.0;
+const DECAL_FADE_TIME: f32 = 4.0;
+/// Fading is done in steps, so the background only has to be redrawn a few times per decal
+const FADE_STEPS: f32 = 4.0;
+
+pub const SPLATTER_COLOR: Color = Color::from_hex(0x3f7a2e);
+
+pub enum DecalKind {
+    /// Blob with droplets around it, as `(offset, radius)`
+    Splatter(Vec<(Vec2, f32)>),
+    /// A dead enemy's last frame
+    Corpse {
+        texture: Texture2D,
+        size: Vec2,
+        flip_x: bool,
+        color: Color,
+    },
+}
+impl DecalKind {
+    pub fn splatter() -> Self {
+        let mut blobs = vec![(Vec2::ZERO, rand::gen_range(4.0, 7.0))];
+        for _ in 0..rand::gen_range(5, 10) {
+            let offset = Vec2::from_angle(rand::gen_range(0.0, PI * 2.0))
+                * rand::gen_range(5.0, 14.0)
+                * vec2(1.0, 0.6);
+            blobs.push((offset.floor(), rand::gen_range(0.5, 2.5)));
+        }
+        DecalKind::Splatter(blobs)
+    }
+}
+
+struct Decal {
+    kind: DecalKind,
+    pos: Vec2,
+    age: f32,
+    /// Time since the decal started fading
+    fade: Option<f32>,
+}
+impl Decal {
+    /// Opacity, rounded to the fade steps
+    fn alpha(&self) -> f32 {
+        match self.fade {
+            Some(time) => ((1.0 - time / DECAL_FADE_TIME) * FADE_STEPS).ceil() / FADE_STEPS,
+            None => 1.0,
+        }
+    }
+    fn draw(&self) {
+        let alpha = self.alpha();
+        match &self.kind {
+            DecalKind::Splatter(blobs) => {
+                for (offset, radius) in blobs {
+                    let pos = (self.pos + *offset).floor();
+                    draw_circle(
+                        pos.x,
+                        pos.y,
+                        *radius,
+                        SPLATTER_COLOR.with_alpha(0.8 * alpha),
+                    );
+                }
+            }
+            DecalKind::Corpse {
+                texture,
+                size,
+                flip_x,
+                color,
+            } => {
+                draw_texture_ex(
+                    texture,
+                    (self.pos.x - size.x / 2.0).floor(),
+                    (self.pos.y - size.y).floor(),
+                    color.with_alpha(color.a * alpha),
+                    DrawTextureParams {
+                        dest_size: Some(*size),
+                        flip_x: *flip_x,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+    }
+}
+
+/// Decals baked into the world background. The clean background is kept so faded decals can be removed.
+pub struct Decals {
+    decals: Vec<Decal>,
+    /// Decals not yet drawn into the background
+    pending: usize,
+    base: Texture2D,
+    /// Where the background texture is drawn in the world
+    origin: Vec2,
+}
+impl Decals {
+    /// Copies the already drawn background of `camera` to restore it from later
+    pub fn new(camera: &Camera2D, origin: Vec2) -> Self {
+        let texture = &camera.render_target.as_ref().unwrap().texture;
+        let mut base_camera = create_camera(texture.width(), texture.height());
+        base_camera.target = camera.target;
+        set_camera(&base_camera);
+        clear_background(BLACK.with_alpha(0.0));
+        draw_texture(texture, origin.x, origin.y, WHITE);
+        Self {
+            decals: Vec::new(),
+            pending: 0,
+            base: base_camera.render_target.unwrap().texture,
+            origin,
+        }
+    }
+    /// Adds a decal with `pos` at its bottom center for corpses, or center for splatters
+    pub fn add(&mut self, kind: DecalKind, pos: Vec2) {
+        self.decals.push(Decal {
+            kind,
+            pos,
+            age: 0.0,
+            fade: None,
+        });
+        self.pending += 1;
+    }
+    /// Ages decals and redraws the background `camera` when anything changed.
+    /// Leaves `camera` set when it had to draw.
+    pub fn update(&mut self, delta_time: f32, camera: &Camera2D) {
+        let mut over_cap = (self.decals.iter())
+            .filter(|f| f.fade.is_none())
+            .count()
+            .saturating_sub(MAX_DECALS);
+        let mut rebake = false;
+        for decal in self.decals.iter_mut() {
+            decal.age += delta_time;
+            let alpha = decal.alpha();
+            match &mut decal.fade {
+                Some(time) => *time += delta_time,
+                None if over_cap > 0 || decal.age > DECAL_LIFETIME => {
+                    over_cap = over_cap.saturating_sub(1);
+                    decal.fade = Some(0.0);
+                }
+                None => {}
+            }
+            rebake |= decal.alpha() != alpha;
+        }
+        self.decals.retain(|f| f.alpha() > 0.0);
+
+        if rebake {
+            set_camera(camera);
+            clear_background(BLACK.with_alpha(0.0));
+            draw_texture(&self.base, self.origin.x, self.origin.y, WHITE);
+            for decal in &self.decals {
+                decal.draw();
+            }
+        } else if self.pending > 0 {
+            set_camera(camera);
+            for decal in &self.decals[self.decals.len() - self.pending..] {
+                decal.draw();
+            }
+        }
+        self.pending = 0;
+    }
+}
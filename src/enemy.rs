@@ -1,20 +1,39 @@
-use std::{collections::VecDeque, f32::consts::PI, sync::LazyLock};
+use std::{
+    collections::VecDeque,
+    f32::consts::PI,
+    sync::{
+        LazyLock,
+        atomic::{AtomicU32, Ordering},
+    },
+};
 
 use crate::{
     assets::{Assets, World},
-    player::{ALIEN_BALL, Player, Projectile, ProjectileType, update_physicsbody},
+    audio::{BOSS_MUSIC, Sfx, Track},
+    decal::DecalKind,
+    modifier::{BURN_DAMAGE_PER_SECOND, CRYO_SLOWDOWN, Element, Modifier, WeaponInstance},
+    particle::{EMERGE_DIRT, Particles},
+    pickup::{AMMO_BOX, ARMOR_SHARD, CREDIT, DropTable, HEALTH_PACK, HEALTH_SHARD, PickupKind},
+    player::{ALIEN_BALL, Player, Projectile, ProjectileType, SHOTGUN, update_physicsbody},
+    utils::interpolate,
 };
 use macroquad::prelude::*;
 
 pub struct EnemyType {
     pub health: f32,
     pub states: Vec<EnemyState>,
+    pub drops: DropTable,
+    /// Tag in enemies.ase played on death. Enemies without one get squashed instead.
+    pub death_tag: &'static str,
+    /// Music played in place of the map's while the enemy is alive, for bosses
+    pub music: Option<&'static Track>,
 }
 pub enum ProjectileFiring {
     None,
     Forwards(&'static ProjectileType),
     Around(&'static ProjectileType, u8),
 }
+#[expect(dead_code)]
 pub enum StateChangeCondition {
     Never,
     Always,
@@ -22,6 +41,7 @@ pub enum StateChangeCondition {
     NearPlayer,
     AnimationFinish,
 }
+#[expect(dead_code)]
 pub enum EnemyMovement {
     Chase,
     None,
@@ -58,6 +78,9 @@ pub static ENEMIES: LazyLock<Vec<EnemyType>> = LazyLock::new(|| {
             },
         ],
         health: 20.0,
+        drops: vec![(0.3, HEALTH_SHARD), (0.1, ARMOR_SHARD), (0.5, CREDIT)],
+        death_tag: "greenoDeath",
+        music: None,
     };
     let dog: EnemyType = EnemyType {
         states: vec![
@@ -79,6 +102,9 @@ pub static ENEMIES: LazyLock<Vec<EnemyType>> = LazyLock::new(|| {
             },
         ],
         health: 9.0,
+        drops: vec![(0.25, HEALTH_SHARD), (0.4, CREDIT)],
+        death_tag: "dogDeath",
+        music: None,
     };
     let shooter: EnemyType = EnemyType {
         states: vec![EnemyState {
@@ -90,6 +116,9 @@ pub static ENEMIES: LazyLock<Vec<EnemyType>> = LazyLock::new(|| {
             damage_on_exit: None,
         }],
         health: 9.0,
+        drops: vec![(0.3, AMMO_BOX), (0.2, ARMOR_SHARD), (0.6, CREDIT)],
+        death_tag: "shooterDeath",
+        music: None,
     };
     let bigo: EnemyType = EnemyType {
         states: vec![
@@ -119,27 +148,83 @@ pub static ENEMIES: LazyLock<Vec<EnemyType>> = LazyLock::new(|| {
             },
         ],
         health: 90.0,
+        drops: vec![
+            (0.35, PickupKind::Weapon(WeaponInstance::new(&SHOTGUN))),
+            (0.5, PickupKind::Modifier(Modifier::Damage)),
+            (1.0, HEALTH_PACK),
+        ],
+        death_tag: "bigDeath",
+        music: Some(&BOSS_MUSIC),
     };
     vec![greeno, dog, shooter, bigo]
 });
 
+static NEXT_ENEMY_ID: AtomicU32 = AtomicU32::new(0);
+
+/// Intensity contributed by each living enemy
+const INTENSITY_PER_ENEMY: f32 = 0.15;
+/// Intensity while any enemy is alive, however few
+const MIN_COMBAT_INTENSITY: f32 = 0.3;
+/// Per second changes of the intensity towards its target
+const INTENSITY_RISE: f32 = 1.5;
+const INTENSITY_FALL: f32 = 0.25;
+
+/// How heated the fighting is, from 0 when no enemies are alive up to 1.
+/// Rises quickly as spawners release enemies and settles slowly once they're all dead.
+pub struct CombatIntensity {
+    level: f32,
+}
+impl CombatIntensity {
+    pub fn new() -> Self {
+        Self { level: 0.0 }
+    }
+    pub fn update(&mut self, delta_time: f32, enemies: &[Enemy]) {
+        let target = if enemies.is_empty() {
+            0.0
+        } else {
+            (enemies.len() as f32 * INTENSITY_PER_ENEMY).clamp(MIN_COMBAT_INTENSITY, 1.0)
+        };
+        self.level = if target > self.level {
+            (self.level + INTENSITY_RISE * delta_time).min(target)
+        } else {
+            (self.level - INTENSITY_FALL * delta_time).max(target)
+        };
+    }
+    pub fn level(&self) -> f32 {
+        self.level
+    }
+}
+
 pub struct Enemy {
+    pub id: u32,
     pub ty: &'static EnemyType,
     pub pos: Vec2,
+    pub last_pos: Vec2,
     pub health: f32,
     pub animation_time: f32,
     pub direction: Vec2,
     pub path: Option<VecDeque<(i16, i16)>>,
     pub time_til_pathfind: f32,
     pub velocity: Vec2,
+    /// Velocity applied from being hit, decays over time
+    pub knockback: Vec2,
     pub emerging: bool,
     pub state: usize,
+    /// Elemental status effect and its remaining time
+    pub status: Option<(Element, f32)>,
+    pub flash_time: f32,
+    /// Damage taken that hasn't been shown as a damage number yet
+    pub unshown_damage: f32,
+    /// Spawner tile the enemy emerged from, telling which spawner group it belongs to
+    pub spawner: Option<(i16, i16)>,
 }
 impl Enemy {
     pub fn new(ty: &'static EnemyType, pos: Vec2) -> Self {
         Self {
+            id: NEXT_ENEMY_ID.fetch_add(1, Ordering::Relaxed),
             ty,
             pos,
+            last_pos: pos,
             health: ty.health,
             animation_time: 0.0,
             direction: vec2(1.0, 0.0),
@@ -147,12 +232,26 @@ impl Enemy {
             time_til_pathfind: 0.0,
             emerging: true,
             velocity: Vec2::ZERO,
+            knockback: Vec2::ZERO,
             state: 0,
+            status: None,
+            flash_time: 0.0,
+            unshown_damage: 0.0,
+            spawner: None,
         }
     }
+    pub fn damage(&mut self, amount: f32) {
+        self.health -= amount;
+        self.unshown_damage += amount;
+        self.flash_time = HIT_FLASH_TIME;
+    }
+    pub fn apply_element(&mut self, element: Element) {
+        self.status = Some((element, element.duration()));
+    }
     fn current_state(&self) -> &'static EnemyState {
         &self.ty.states[self.state % self.ty.states.len()]
     }
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         delta_time: f32,
@@ -160,13 +259,43 @@ impl Enemy {
         world: &World,
         assets: &Assets,
         projectiles: &mut Vec<Projectile>,
+        particles: &mut Particles,
+        sfx: &mut Vec<(Sfx, Vec2)>,
     ) {
+        self.last_pos = self.pos;
         self.animation_time += delta_time;
+        self.flash_time -= delta_time;
         if self.emerging && self.animation_time < HOLE_TIME {
+            // debris kicked up while the hole opens
+            if self.animation_time < HOLE_EMERGE_TIME && rand::gen_range(0.0, 1.0) < 0.3 {
+                let dir = Vec2::from_angle(rand::gen_range(0.0, PI * 2.0));
+                let radius = self.animation_time / HOLE_EMERGE_TIME * MAX_HOLE_DIAMETER;
+                let edge = dir * vec2(radius, radius / 2.0);
+                particles.emit(&EMERGE_DIRT, self.pos + vec2(0.0, 8.0) + edge, dir);
+            }
             return;
         } else if self.emerging {
             self.emerging = false;
         }
+        if self.knockback.length_squared() > 1.0 {
+            self.pos = update_physicsbody(self.pos, &mut self.knockback, delta_time, world);
+            self.knockback = self.knockback.lerp(Vec2::ZERO, 10.0 * delta_time);
+        }
+        let mut speed_multiplier = 1.0;
+        if let Some((element, time)) = &mut self.status {
+            match element {
+                Element::Burn => {
+                    // burning doesn't flash, it would flicker constantly
+                    self.health -= BURN_DAMAGE_PER_SECOND * delta_time;
+                    self.unshown_damage += BURN_DAMAGE_PER_SECOND * delta_time;
+                }
+                Element::Cryo => speed_multiplier = CRYO_SLOWDOWN,
+            }
+            *time -= delta_time;
+            if *time <= 0.0 {
+                self.status = None;
+            }
+        }
         let delta = player.pos - self.pos;
         let mut hit_wall = false;
         let mut target = player.pos + 8.0;
@@ -197,7 +326,8 @@ impl Enemy {
         let distance = target.distance_squared(self.pos);
         if distance > 0.0 && !matches!(self.current_state().movement, EnemyMovement::None) {
             self.direction = (target - self.pos).normalize();
-            self.velocity = (target - self.pos).normalize() * self.current_state().speed;
+            self.velocity =
+                (target - self.pos).normalize() * self.current_state().speed * speed_multiplier;
             let v = self.velocity;
             self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
             if self.velocity.length_squared() < v.length_squared() {
@@ -224,31 +354,20 @@ impl Enemy {
             if let Some(damage) = self.current_state().damage_on_exit
                 && player.pos.distance_squared(self.pos) < 144.0
             {
-                player.health -= damage;
+                player.damage(damage, sfx);
             }
             match &self.current_state().projectile_firing {
                 ProjectileFiring::None => {}
                 ProjectileFiring::Forwards(projectile) => {
-                    let new = Projectile {
-                        ty: projectile,
-                        pos: self.pos,
-                        dir: self.direction,
-                        time: 0.0,
-                        friendly: false,
-                    };
+                    let new = Projectile::new(projectile, self.pos, self.direction, false);
                     projectiles.push(new);
                 }
                 ProjectileFiring::Around(projectile, amt) => {
                     let angle = 2.0 * PI / *amt as f32;
                     for i in 0..*amt {
                         let angle = angle * i as f32 + self.direction.to_angle();
-                        let new = Projectile {
-                            ty: projectile,
-                            pos: self.pos,
-                            dir: Vec2::from_angle(angle),
-                            time: 0.0,
-                            friendly: false,
-                        };
+                        let new =
+                            Projectile::new(projectile, self.pos, Vec2::from_angle(angle), false);
                         projectiles.push(new);
                     }
                 }
@@ -257,15 +376,18 @@ impl Enemy {
             self.animation_time = 0.0;
         }
     }
-    pub fn draw(&mut self, assets: &Assets) {
+    pub fn draw_pos(&self, alpha: f32) -> Vec2 {
+        interpolate(self.last_pos, self.pos, alpha)
+    }
+    pub fn draw(&mut self, assets: &Assets, alpha: f32) {
+        let pos = self.draw_pos(alpha);
         if self.emerging && self.animation_time < HOLE_TIME {
-            let max_hole_diameter = 20.0;
-            let diameter = (self.animation_time / HOLE_EMERGE_TIME * max_hole_diameter)
-                .min(max_hole_diameter)
+            let diameter = (self.animation_time / HOLE_EMERGE_TIME * MAX_HOLE_DIAMETER)
+                .min(MAX_HOLE_DIAMETER)
                 .floor();
             draw_ellipse(
-                self.pos.x.floor(),
-                self.pos.y.floor() + 8.0,
+                pos.x.floor(),
+                pos.y.floor() + 8.0,
                 diameter,
                 diameter / 2.0,
                 0.0,
@@ -274,7 +396,7 @@ impl Enemy {
             if self.animation_time > HOLE_EMERGE_TIME {
                 let amt = (self.animation_time - HOLE_EMERGE_TIME) / (HOLE_TIME - HOLE_EMERGE_TIME);
                 let amt = (amt - 1.0).powi(5) + 1.0;
-                let pos = self.pos.floor() + vec2(0.0, 13.0 - amt * 13.0);
+                let pos = pos.floor() + vec2(0.0, 13.0 - amt * 13.0);
                 draw_texture_ex(
                     assets.enemies.animations[self.current_state().animation_id]
                         .get_at_time((self.animation_time * 1000.0) as u32),
@@ -289,20 +411,30 @@ impl Enemy {
             }
             return;
         }
+        let tint = match self.status {
+            Some((element, _)) => {
+                Color::from_vec(WHITE.to_vec().lerp(element.color().to_vec(), 0.6))
+            }
+            None => WHITE,
+        };
+        if self.flash_time > 0.0 {
+            gl_use_material(&assets.flash_material);
+        }
         draw_texture_ex(
             assets.enemies.animations[self.current_state().animation_id]
                 .get_at_time((self.animation_time * 1000.0) as u32),
-            self.pos.x.floor() - 16.0,
-            self.pos.y.floor() - 16.0,
-            WHITE,
+            pos.x.floor() - 16.0,
+            pos.y.floor() - 16.0,
+            if self.flash_time > 0.0 { WHITE } else { tint },
             DrawTextureParams {
                 flip_x: self.direction.x > 0.0,
                 ..Default::default()
             },
         );
+        gl_use_default_material();
         let width = 25.0;
         let height = 4.0;
-        let pos = self.pos.floor() - 16.0 + vec2(0.0, -4.0) + (32.0 - width) / 2.0;
+        let pos = pos.floor() - 16.0 + vec2(0.0, -4.0) + (32.0 - width) / 2.0;
         draw_rectangle(pos.x - 1.0, pos.y - 1.0, width + 2.0, height + 2.0, BLACK);
         draw_rectangle(
             pos.x,
@@ -313,6 +445,91 @@ impl Enemy {
         );
     }
 }
+
+/// A killed enemy playing its death animation, before being left behind as a decal
+pub struct Corpse {
+    pos: Vec2,
+    /// Index of the death animation in `Assets::enemies`
+    animation: Option<usize>,
+    /// Frame the enemy died on, squashed when there is no death animation
+    texture: Texture2D,
+    flip_x: bool,
+    time: f32,
+}
+impl Corpse {
+    pub fn new(enemy: &Enemy, assets: &Assets) -> Self {
+        Self {
+            pos: enemy.pos,
+            animation: assets.enemies.tag_index(enemy.ty.death_tag),
+            texture: assets.enemies.animations[enemy.current_state().animation_id]
+                .get_at_time((enemy.animation_time * 1000.0) as u32)
+                .clone(),
+            flip_x: enemy.direction.x > 0.0,
+            time: 0.0,
+        }
+    }
+    /// Current frame, its size, and its tint
+    fn frame<'a>(&'a self, assets: &'a Assets) -> (&'a Texture2D, Vec2, Color) {
+        match self.animation {
+            Some(index) => {
+                let animation = &assets.enemies.animations[index];
+                let time = (self.time * 1000.0) as u32;
+                let time = time.min(animation.total_length.saturating_sub(1));
+                (animation.get_at_time(time), vec2(32.0, 32.0), WHITE)
+            }
+            None => {
+                let amount = (self.time / SQUASH_TIME).min(1.0);
+                let size = vec2(32.0 * (1.0 + amount * 0.3), 32.0 * (1.0 - amount * 0.6));
+                let color = Color::from_vec(WHITE.to_vec().lerp(CORPSE_COLOR.to_vec(), amount));
+                (&self.texture, size.floor(), color)
+            }
+        }
+    }
+    /// Returns the decal to leave behind once the death animation has finished
+    pub fn update(&mut self, delta_time: f32, assets: &Assets) -> Option<DecalKind> {
+        self.time += delta_time;
+        let finished = match self.animation {
+            Some(index) => {
+                self.time * 1000.0 >= assets.enemies.animations[index].total_length as f32
+            }
+            None => self.time >= SQUASH_TIME,
+        };
+        if !finished {
+            return None;
+        }
+        let (texture, size, color) = self.frame(assets);
+        Some(DecalKind::Corpse {
+            texture: texture.clone(),
+            size,
+            flip_x: self.flip_x,
+            color,
+        })
+    }
+    /// Position of the bottom center of the sprite
+    pub fn base(&self) -> Vec2 {
+        self.pos + vec2(0.0, 16.0)
+    }
+    pub fn draw(&self, assets: &Assets) {
+        let (texture, size, color) = self.frame(assets);
+        let base = self.base();
+        draw_texture_ex(
+            texture,
+            (base.x - size.x / 2.0).floor(),
+            (base.y - size.y).floor(),
+            color,
+            DrawTextureParams {
+                dest_size: Some(size),
+                flip_x: self.flip_x,
+                ..Default::default()
+            },
+        );
+    }
+}
+const SQUASH_TIME: f32 = 0.3;
+const CORPSE_COLOR: Color = Color::new(0.35, 0.4, 0.35, 1.0);
+
 pub const HEALTHBAR_COLOR: Color = Color::from_hex(0x39741f);
+pub const HIT_FLASH_TIME: f32 = 0.08;
 const HOLE_EMERGE_TIME: f32 = 0.7;
 const HOLE_TIME: f32 = 1.8;
+const MAX_HOLE_DIAMETER: f32 = 20.0;
@@ -0,0 +1,178 @@
+use macroquad::prelude::*;
+
+/// Size of a character's cell in the font sheet. Glyphs are left aligned in their cell.
+const CELL_SIZE: f32 = 6.0;
+const GLYPH_HEIGHT: f32 = 5.0;
+const LINE_HEIGHT: f32 = 7.0;
+const LETTER_SPACING: f32 = 1.0;
+const SPACE_WIDTH: f32 = 2.0;
+/// The sheet holds printable ASCII, 16 characters per row, starting at the space
+const FIRST_CHAR: u8 = b' ';
+const SHEET_COLUMNS: usize = 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Copy)]
+pub struct TextStyle {
+    /// Size of a font pixel, kept whole so text stays pixel-perfect
+    pub scale: f32,
+    pub color: Color,
+    /// How lines are placed relative to the x they're drawn at
+    pub align: Align,
+    pub outline: Option<Color>,
+    /// Width lines are broken at, between words
+    pub wrap: Option<f32>,
+}
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            color: WHITE,
+            align: Align::Left,
+            outline: None,
+            wrap: None,
+        }
+    }
+}
+
+/// Proportional bitmap font, with each glyph's width measured from the sheet
+pub struct PixelFont {
+    texture: Texture2D,
+    widths: Vec<f32>,
+}
+impl PixelFont {
+    pub fn from_image(image: &Image) -> Self {
+        let cell = CELL_SIZE as u32;
+        let count =
+            (image.width as usize / cell as usize) * (image.height as usize / cell as usize);
+        let widths = (0..count)
+            .map(|i| {
+                let (cx, cy) = (
+                    (i % SHEET_COLUMNS) as u32 * cell,
+                    (i / SHEET_COLUMNS) as u32 * cell,
+                );
+                // rightmost column with a pixel in it
+                (0..cell)
+                    .rev()
+                    .find(|x| (0..cell).any(|y| image.get_pixel(cx + x, cy + y).a > 0.0))
+                    .map_or(SPACE_WIDTH, |x| (x + 1) as f32)
+            })
+            .collect();
+        let texture = Texture2D::from_image(image);
+        texture.set_filter(FilterMode::Nearest);
+        Self { texture, widths }
+    }
+    fn glyph(&self, c: char) -> Option<usize> {
+        let index = (c as usize).checked_sub(FIRST_CHAR as usize)?;
+        (index < self.widths.len()).then_some(index)
+    }
+    /// Width of a single line at a scale of 1
+    fn line_width(&self, line: &str) -> f32 {
+        let width: f32 = (line.chars())
+            .map(|c| {
+                self.glyph(c)
+                    .map_or(0.0, |i| self.widths[i] + LETTER_SPACING)
+            })
+            .sum();
+        (width - LETTER_SPACING).max(0.0)
+    }
+    /// Splits text into lines at newlines, and between words where they'd pass `style.wrap`
+    fn lines(&self, text: &str, style: &TextStyle) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let Some(max_width) = style.wrap else {
+                lines.push(paragraph.to_string());
+                continue;
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{line} {word}")
+                };
+                if !line.is_empty() && self.line_width(&candidate) * style.scale > max_width {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                } else {
+                    line = candidate;
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+    /// Size `text` takes up when drawn with `style`
+    pub fn measure(&self, text: &str, style: &TextStyle) -> Vec2 {
+        let scale = style.scale.floor().max(1.0);
+        let lines = self.lines(text, &TextStyle { scale, ..*style });
+        let width = (lines.iter())
+            .map(|line| self.line_width(line))
+            .fold(0.0, f32::max);
+        let height = lines.len() as f32 * LINE_HEIGHT - (LINE_HEIGHT - GLYPH_HEIGHT);
+        vec2(width, height) * scale
+    }
+    fn draw_line(&self, line: &str, x: f32, y: f32, scale: f32, color: Color) {
+        let mut x = x;
+        for c in line.chars() {
+            let Some(index) = self.glyph(c) else {
+                continue;
+            };
+            let width = self.widths[index];
+            let source = Rect::new(
+                (index % SHEET_COLUMNS) as f32 * CELL_SIZE,
+                (index / SHEET_COLUMNS) as f32 * CELL_SIZE,
+                width,
+                GLYPH_HEIGHT,
+            );
+            draw_texture_ex(
+                &self.texture,
+                x,
+                y,
+                color,
+                DrawTextureParams {
+                    source: Some(source),
+                    dest_size: Some(source.size() * scale),
+                    ..Default::default()
+                },
+            );
+            x += (width + LETTER_SPACING) * scale;
+        }
+    }
+}
+
+/// Draws text with its top at `y`, placed around `x` according to `style.align`
+pub fn draw_pixel_text(font: &PixelFont, text: &str, x: f32, y: f32, style: &TextStyle) {
+    let scale = style.scale.floor().max(1.0);
+    let style = TextStyle { scale, ..*style };
+    for (i, line) in font.lines(text, &style).iter().enumerate() {
+        let width = font.line_width(line) * scale;
+        let left = match style.align {
+            Align::Left => x,
+            Align::Center => x - width / 2.0,
+            Align::Right => x - width,
+        }
+        .floor();
+        let top = (y + i as f32 * LINE_HEIGHT * scale).floor();
+        if let Some(outline) = style.outline {
+            for (dx, dy) in [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ] {
+                let offset = vec2(dx as f32, dy as f32) * scale;
+                font.draw_line(line, left + offset.x, top + offset.y, scale, outline);
+            }
+        }
+        font.draw_line(line, left, top, scale, style.color);
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use macroquad::{miniquad::window::screen_size, prelude::*};
+
+use crate::utils::*;
+
+/// Distance kept from the window edges, in HUD pixels
+const SAFE_MARGIN: f32 = 8.0;
+/// Gap between elements stacked at the same anchor, in HUD pixels
+const SPACING: f32 = 2.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Anchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    BottomCenter,
+}
+impl Anchor {
+    /// Fraction of the safe area the anchor sits at
+    fn point(&self) -> Vec2 {
+        match self {
+            Anchor::TopLeft => vec2(0.0, 0.0),
+            Anchor::TopCenter => vec2(0.5, 0.0),
+            Anchor::TopRight => vec2(1.0, 0.0),
+            Anchor::BottomCenter => vec2(0.5, 1.0),
+        }
+    }
+    fn is_bottom(&self) -> bool {
+        self.point().y == 1.0
+    }
+}
+
+/// Places HUD elements inside the window's safe area for a single frame.
+/// Elements sharing an anchor are stacked away from the window edge, so they never overlap.
+pub struct HudLayout {
+    /// Size of a HUD pixel in the window, always whole
+    pub scale: f32,
+    safe_area: Rect,
+    /// Space used so far at each anchor, in HUD pixels
+    stacks: HashMap<Anchor, f32>,
+}
+impl HudLayout {
+    /// `ui_scale` multiplies the scale the pixel camera is drawn at
+    pub fn new(ui_scale: f32) -> Self {
+        let (width, height) = screen_size();
+        let scale = ((width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT) * ui_scale)
+            .floor()
+            .max(1.0);
+        let margin = SAFE_MARGIN * scale;
+        Self {
+            scale,
+            safe_area: Rect::new(margin, margin, width - margin * 2.0, height - margin * 2.0),
+            stacks: HashMap::new(),
+        }
+    }
+    /// Reserves space for an element of `size` HUD pixels at `anchor`, returning its top left in window space
+    pub fn place(&mut self, anchor: Anchor, size: Vec2) -> Vec2 {
+        let size = size * self.scale;
+        let used = self.stacks.entry(anchor).or_default();
+        let offset = *used * self.scale;
+        *used += size.y / self.scale + SPACING;
+
+        let point = anchor.point();
+        let x = self.safe_area.x + (self.safe_area.w - size.x) * point.x;
+        let y = if anchor.is_bottom() {
+            self.safe_area.bottom() - size.y - offset
+        } else {
+            self.safe_area.y + offset
+        };
+        vec2(x, y).floor()
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::{HashMap, HashSet};
+
+use macroquad::prelude::*;
+
+use crate::utils::*;
+
+/// Distance from the player the crosshair is drawn at when aiming with a gamepad
+pub const GAMEPAD_CROSSHAIR_DISTANCE: f32 = 48.0;
+const BINDINGS_FILE: &str = "bindings.cfg";
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Fire,
+    Melee,
+    Interact,
+    Dash,
+    Map,
+    Pause,
+    /// Picks the selected menu option
+    Confirm,
+    /// Restores the selected action's default bindings in the controls menu
+    ResetBinding,
+}
+pub static ACTIONS: &[Action] = &[
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Fire,
+    Action::Melee,
+    Action::Interact,
+    Action::Dash,
+    Action::Map,
+    Action::Pause,
+    Action::Confirm,
+    Action::ResetBinding,
+];
+impl Action {
+    /// Name used in the bindings file
+    fn id(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Fire => "fire",
+            Action::Melee => "melee",
+            Action::Interact => "interact",
+            Action::Dash => "dash",
+            Action::Map => "map",
+            Action::Pause => "pause",
+            Action::Confirm => "confirm",
+            Action::ResetBinding => "reset_binding",
+        }
+    }
+    /// Name shown in menus
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::MoveUp => "Move up",
+            Action::MoveDown => "Move down",
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Fire => "Fire",
+            Action::Melee => "Melee",
+            Action::Interact => "Interact",
+            Action::Dash => "Dash",
+            Action::Map => "Map",
+            Action::Pause => "Pause",
+            Action::Confirm => "Confirm",
+            Action::ResetBinding => "Reset binding",
+        }
+    }
+}
+
+const MOUSE_BUTTONS: [MouseButton; 3] =
+    [MouseButton::Left, MouseButton::Right, MouseButton::Middle];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+impl Binding {
+    fn is_down(&self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_down(*key),
+            Binding::Mouse(button) => is_mouse_button_down(*button),
+        }
+    }
+    fn is_pressed(&self) -> bool {
+        match self {
+            Binding::Key(key) => is_key_pressed(*key),
+            Binding::Mouse(button) => is_mouse_button_pressed(*button),
+        }
+    }
+    /// Gets the binding for whatever key or mouse button was pressed this frame
+    pub fn get_pressed() -> Option<Self> {
+        if let Some(key) = get_last_key_pressed()
+            && BINDABLE_KEYS.contains(&key)
+        {
+            return Some(Binding::Key(key));
+        }
+        MOUSE_BUTTONS
+            .into_iter()
+            .find(|button| is_mouse_button_pressed(*button))
+            .map(Binding::Mouse)
+    }
+    /// Name shown in menus
+    pub fn label(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{key:?}"),
+            Binding::Mouse(button) => format!("Mouse {button:?}"),
+        }
+    }
+    fn parse(text: &str) -> Option<Self> {
+        let (kind, name) = text.trim().split_once(':')?;
+        match kind {
+            "Key" => BINDABLE_KEYS
+                .iter()
+                .find(|key| format!("{key:?}") == name)
+                .map(|key| Binding::Key(*key)),
+            "Mouse" => MOUSE_BUTTONS
+                .into_iter()
+                .find(|button| format!("{button:?}") == name)
+                .map(Binding::Mouse),
+            _ => None,
+        }
+    }
+}
+impl std::fmt::Display for Binding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "Key:{key:?}"),
+            Binding::Mouse(button) => write!(f, "Mouse:{button:?}"),
+        }
+    }
+}
+
+#[rustfmt::skip]
+static BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+    KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right,
+    KeyCode::Space, KeyCode::Enter, KeyCode::Tab, KeyCode::Escape, KeyCode::Backspace,
+    KeyCode::LeftShift, KeyCode::RightShift, KeyCode::LeftControl, KeyCode::RightControl,
+    KeyCode::LeftAlt, KeyCode::RightAlt,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Slash, KeyCode::Semicolon, KeyCode::Apostrophe,
+    KeyCode::Minus, KeyCode::Equal, KeyCode::LeftBracket, KeyCode::RightBracket,
+    KeyCode::Backslash, KeyCode::GraveAccent,
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+    KeyCode::Kp0, KeyCode::Kp1, KeyCode::Kp2, KeyCode::Kp3, KeyCode::Kp4,
+    KeyCode::Kp5, KeyCode::Kp6, KeyCode::Kp7, KeyCode::Kp8, KeyCode::Kp9,
+];
+
+/// Keyboard and mouse bindings for every action
+#[derive(Clone)]
+pub struct Bindings {
+    pub map: HashMap<Action, Vec<Binding>>,
+}
+impl Default for Bindings {
+    fn default() -> Self {
+        let key = Binding::Key;
+        Self {
+            map: HashMap::from([
+                (Action::MoveUp, vec![key(KeyCode::W), key(KeyCode::Up)]),
+                (Action::MoveDown, vec![key(KeyCode::S), key(KeyCode::Down)]),
+                (Action::MoveLeft, vec![key(KeyCode::A), key(KeyCode::Left)]),
+                (
+                    Action::MoveRight,
+                    vec![key(KeyCode::D), key(KeyCode::Right)],
+                ),
+                (Action::Fire, vec![Binding::Mouse(MouseButton::Left)]),
+                (
+                    Action::Melee,
+                    vec![Binding::Mouse(MouseButton::Right), key(KeyCode::F)],
+                ),
+                (Action::Interact, vec![key(KeyCode::E)]),
+                (
+                    Action::Dash,
+                    vec![key(KeyCode::Space), key(KeyCode::LeftShift)],
+                ),
+                (Action::Map, vec![key(KeyCode::Tab), key(KeyCode::M)]),
+                (Action::Pause, vec![key(KeyCode::Escape)]),
+                (Action::Confirm, vec![key(KeyCode::Enter)]),
+                (Action::ResetBinding, vec![key(KeyCode::Backspace)]),
+            ]),
+        }
+    }
+}
+impl Bindings {
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.map.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+    /// Restores the default bindings of a single action
+    pub fn reset(&mut self, action: Action) {
+        let default = Self::default().get(action).to_vec();
+        self.map.insert(action, default);
+    }
+    /// Parses bindings from lines of `action = Kind:Name, Kind:Name`.
+    /// Actions missing from the text keep their default bindings.
+    fn parse(text: &str) -> Self {
+        let mut bindings = Self::default();
+        for (id, value) in config_entries(text) {
+            let Some(action) = ACTIONS.iter().find(|f| f.id() == id) else {
+                continue;
+            };
+            let list = value.split(',').filter_map(Binding::parse).collect();
+            bindings.map.insert(*action, list);
+        }
+        bindings
+    }
+    fn serialize(&self) -> String {
+        let mut text = String::from("# action = Key:Name, Mouse:Name\n");
+        for action in ACTIONS {
+            let list: Vec<String> = self.get(*action).iter().map(|f| f.to_string()).collect();
+            text += &format!("{} = {}\n", action.id(), list.join(", "));
+        }
+        text
+    }
+    /// Loads bindings from the config directory, falling back to the defaults
+    pub fn load() -> Self {
+        std::fs::read_to_string(config_dir().join(BINDINGS_FILE))
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+    pub fn save(&self) -> std::io::Result<()> {
+        let dir = config_dir();
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(BINDINGS_FILE), self.serialize())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AimSource {
+    Mouse,
+    #[cfg_attr(not(feature = "gamepad"), expect(dead_code))]
+    Gamepad,
+}
+
+/// Input state for a single frame, merged from keyboard, mouse and gamepad
+pub struct Input {
+    pub bindings: Bindings,
+    pub movement: Vec2,
+    /// Aim direction from the player, normalized
+    pub aim: Vec2,
+    pub aim_source: AimSource,
+    /// Mouse position in world space
+    pub mouse: Vec2,
+    /// Mouse position in the window, for hit-testing menus
+    pub screen_mouse: Vec2,
+    /// Offset of the crosshair from the center of the view, for camera look-ahead
+    pub look: Vec2,
+    down: HashSet<Action>,
+    pressed: HashSet<Action>,
+    /// Presses no simulation step saw yet, kept for the next frame
+    carried: HashSet<Action>,
+    last_mouse: Vec2,
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<gamepad::Gamepad>,
+}
+impl Input {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            movement: Vec2::ZERO,
+            aim: Vec2::X,
+            aim_source: AimSource::Mouse,
+            mouse: Vec2::ZERO,
+            screen_mouse: Vec2::ZERO,
+            look: Vec2::ZERO,
+            down: HashSet::new(),
+            pressed: HashSet::new(),
+            carried: HashSet::new(),
+            last_mouse: Vec2::ZERO,
+            #[cfg(feature = "gamepad")]
+            gamepad: gamepad::Gamepad::new(),
+        }
+    }
+    pub fn is_down(&self, action: Action) -> bool {
+        self.down.contains(&action)
+    }
+    /// Whether the action was started this frame
+    pub fn is_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+    /// Whether the action was started this frame, using the press up so nothing else sees it
+    pub fn take_pressed(&mut self, action: Action) -> bool {
+        self.pressed.remove(&action)
+    }
+    /// Uses up this frame's presses, once a simulation step has seen them
+    pub fn consume_presses(&mut self) {
+        self.pressed.clear();
+    }
+    /// Keeps this frame's presses for the next frame, when no simulation step ran to see them
+    pub fn carry_presses(&mut self) {
+        self.carried = std::mem::take(&mut self.pressed);
+    }
+    /// Polls all devices. Mouse aim points from `player_center` to the cursor, both in world space.
+    pub fn update(&mut self, letterbox: &Letterbox, camera: &Camera2D, player_center: Vec2) {
+        let screen_mouse = Vec2::from(mouse_position());
+        let mouse = letterbox.screen_to_world(screen_mouse, camera);
+        self.mouse = mouse;
+        self.screen_mouse = screen_mouse;
+        self.down.clear();
+        self.pressed = std::mem::take(&mut self.carried);
+        for action in ACTIONS {
+            let bindings = self.bindings.get(*action);
+            if bindings.iter().any(Binding::is_down) {
+                self.down.insert(*action);
+            }
+            if bindings.iter().any(Binding::is_pressed) {
+                self.pressed.insert(*action);
+            }
+        }
+
+        if screen_mouse.distance_squared(self.last_mouse) > 0.0 || self.is_down(Action::Fire) {
+            self.aim_source = AimSource::Mouse;
+        }
+        self.last_mouse = screen_mouse;
+
+        self.movement = Vec2::ZERO;
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = &mut self.gamepad {
+            let state = gamepad.poll();
+            self.movement += state.movement;
+            if let Some(aim) = state.aim {
+                self.aim_source = AimSource::Gamepad;
+                self.aim = aim;
+            }
+            self.down.extend(state.down);
+            self.pressed.extend(state.pressed);
+        }
+        for (action, dir) in [
+            (Action::MoveLeft, -Vec2::X),
+            (Action::MoveRight, Vec2::X),
+            (Action::MoveUp, -Vec2::Y),
+            (Action::MoveDown, Vec2::Y),
+        ] {
+            if self.is_down(action) {
+                self.movement += dir;
+            }
+        }
+        if self.movement.length() > 1.0 {
+            self.movement = self.movement.normalize();
+        }
+
+        match self.aim_source {
+            AimSource::Mouse => {
+                self.aim = (mouse - player_center).normalize_or(self.aim);
+                self.look = letterbox.screen_to_pixel(screen_mouse)
+                    - vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0;
+            }
+            AimSource::Gamepad => self.look = self.aim * GAMEPAD_CROSSHAIR_DISTANCE,
+        }
+    }
+}
+
+#[cfg(feature = "gamepad")]
+mod gamepad {
+    use std::collections::HashSet;
+
+    use gilrs::{Axis, Button, Gilrs};
+    use macroquad::prelude::*;
+
+    use super::Action;
+
+    /// Sticks below this magnitude are treated as centered
+    const STICK_DEADZONE: f32 = 0.2;
+    /// Right stick magnitude needed to switch to and steer gamepad aiming
+    const AIM_THRESHOLD: f32 = 0.4;
+    /// Gamepad buttons aren't rebindable
+    const BUTTONS: &[(Button, Action)] = &[
+        (Button::RightTrigger2, Action::Fire),
+        (Button::RightTrigger, Action::Melee),
+        (Button::LeftTrigger2, Action::Melee),
+        (Button::South, Action::Interact),
+        (Button::South, Action::Confirm),
+        (Button::North, Action::ResetBinding),
+        (Button::LeftTrigger, Action::Dash),
+        (Button::East, Action::Dash),
+        (Button::Select, Action::Map),
+        (Button::Start, Action::Pause),
+        (Button::DPadUp, Action::MoveUp),
+        (Button::DPadDown, Action::MoveDown),
+        (Button::DPadLeft, Action::MoveLeft),
+        (Button::DPadRight, Action::MoveRight),
+    ];
+
+    pub struct GamepadState {
+        pub movement: Vec2,
+        /// Normalized aim direction, if the right stick is pushed far enough
+        pub aim: Option<Vec2>,
+        pub down: HashSet<Action>,
+        pub pressed: HashSet<Action>,
+    }
+    pub struct Gamepad {
+        gilrs: Gilrs,
+        last_down: HashSet<Action>,
+    }
+    impl Gamepad {
+        /// Returns `None` when gamepads aren't supported on this system
+        pub fn new() -> Option<Self> {
+            Some(Self {
+                gilrs: Gilrs::new().ok()?,
+                last_down: HashSet::new(),
+            })
+        }
+        pub fn poll(&mut self) -> GamepadState {
+            // events have to be drained for the cached gamepad state to update
+            while self.gilrs.next_event().is_some() {}
+
+            let mut state = GamepadState {
+                movement: Vec2::ZERO,
+                aim: None,
+                down: HashSet::new(),
+                pressed: HashSet::new(),
+            };
+            if let Some((_, gamepad)) = self.gilrs.gamepads().next() {
+                // gilrs uses y up
+                let movement = vec2(
+                    gamepad.value(Axis::LeftStickX),
+                    -gamepad.value(Axis::LeftStickY),
+                );
+                if movement.length() > STICK_DEADZONE {
+                    state.movement = movement;
+                }
+                let aim = vec2(
+                    gamepad.value(Axis::RightStickX),
+                    -gamepad.value(Axis::RightStickY),
+                );
+                if aim.length() > AIM_THRESHOLD {
+                    state.aim = Some(aim.normalize());
+                }
+                for (button, action) in BUTTONS {
+                    if gamepad.is_pressed(*button) {
+                        state.down.insert(*action);
+                    }
+                }
+            }
+            state.pressed = state.down.difference(&self.last_down).copied().collect();
+            self.last_down = state.down.clone();
+            state
+        }
+    }
+}
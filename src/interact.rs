@@ -0,0 +1,88 @@
+use macroquad::prelude::*;
+
+use crate::{
+    audio::Sfx,
+    input::{Action, Input},
+    player::Player,
+};
+
+/// What an interactable offers the player while they're in reach
+pub struct Prompt {
+    pub text: String,
+    /// Where the reach is measured from
+    pub pos: Vec2,
+    pub radius: f32,
+    /// Higher priorities win over closer interactables
+    pub priority: i32,
+    /// Seconds interact has to be held for, or `None` to interact on press
+    pub hold: Option<f32>,
+}
+
+/// Something the player can use with the interact action
+pub trait Interactable {
+    /// The prompt to show, or `None` while it can't be used
+    fn prompt(&self, player: &Player) -> Option<Prompt>;
+    /// Pushes any sounds it makes, and where, to `sfx`
+    fn interact(&mut self, player: &mut Player, sfx: &mut Vec<(Sfx, Vec2)>);
+}
+
+/// Tracks which interactable is targeted, identified by `T`, and how long interact has been held on it
+pub struct Interaction<T> {
+    target: Option<(T, Prompt)>,
+    hold_time: f32,
+}
+impl<T: Copy + PartialEq> Interaction<T> {
+    pub fn new() -> Self {
+        Self {
+            target: None,
+            hold_time: 0.0,
+        }
+    }
+    /// Targets the highest priority candidate in reach, closest first.
+    /// Returns the target once it's been interacted with.
+    pub fn update(
+        &mut self,
+        delta_time: f32,
+        candidates: impl Iterator<Item = (T, Prompt)>,
+        player: &Player,
+        input: &Input,
+    ) -> Option<T> {
+        let center = player.pos + 8.0;
+        let target = candidates
+            .filter(|(_, prompt)| center.distance(prompt.pos) < prompt.radius)
+            .min_by(|(_, a), (_, b)| {
+                b.priority.cmp(&a.priority).then(
+                    center
+                        .distance_squared(a.pos)
+                        .total_cmp(&center.distance_squared(b.pos)),
+                )
+            });
+        let same_target = matches!((&target, &self.target), (Some((a, _)), Some((b, _))) if a == b);
+        if !same_target {
+            self.hold_time = 0.0;
+        }
+        self.target = target;
+        let (id, prompt) = self.target.as_ref()?;
+        let done = match prompt.hold {
+            None => input.is_pressed(Action::Interact),
+            Some(hold) => {
+                if input.is_down(Action::Interact) {
+                    self.hold_time += delta_time;
+                } else {
+                    self.hold_time = 0.0;
+                }
+                self.hold_time >= hold
+            }
+        };
+        if done {
+            self.hold_time = 0.0;
+        }
+        done.then_some(*id)
+    }
+    /// The targeted prompt and how far interact has been held on it, from 0 to 1
+    pub fn prompt(&self) -> Option<(&Prompt, f32)> {
+        let (_, prompt) = self.target.as_ref()?;
+        let progress = prompt.hold.map_or(0.0, |hold| self.hold_time / hold);
+        Some((prompt, progress.min(1.0)))
+    }
+}
@@ -0,0 +1,147 @@
+use std::f32::consts::PI;
+
+use macroquad::{models::Vertex, prelude::*};
+
+use crate::{
+    assets::{Assets, World},
+    utils::*,
+};
+
+/// Rays cast per occluded light
+const LIGHT_RAYS: usize = 48;
+/// How far light bleeds into walls, so their edges get lit
+const WALL_BLEED: f32 = 4.0;
+
+pub const DEFAULT_AMBIENT: Color = Color::new(0.3, 0.3, 0.4, 1.0);
+pub const PLAYER_LIGHT: Light = Light {
+    radius: 110.0,
+    color: Color::new(0.8, 0.8, 0.75, 1.0),
+    occluded: true,
+};
+pub const FRIENDLY_PROJECTILE_LIGHT: Light = Light::new(28.0, Color::new(0.3, 0.5, 0.6, 1.0));
+pub const HOSTILE_PROJECTILE_LIGHT: Light = Light::new(28.0, Color::new(0.4, 0.6, 0.2, 1.0));
+pub const ENEMY_GLOW_COLOR: Color = Color::new(0.2, 0.35, 0.15, 1.0);
+
+#[derive(Clone, Copy)]
+pub struct Light {
+    pub radius: f32,
+    pub color: Color,
+    /// Whether walls cast shadows from this light
+    pub occluded: bool,
+}
+impl Light {
+    /// Gets the lamp placed by a tile in the interactable layer
+    pub fn from_tile(tile: i16) -> Option<Self> {
+        let color = match tile {
+            136 => Color::from_hex(0xffe0b0),
+            137 => Color::from_hex(0xff4a3a),
+            138 => Color::from_hex(0x6ad7ff),
+            139 => Color::from_hex(0x7fd14a),
+            _ => return None,
+        };
+        Some(Light {
+            radius: 120.0,
+            color,
+            occluded: true,
+        })
+    }
+    pub const fn new(radius: f32, color: Color) -> Self {
+        Self {
+            radius,
+            color,
+            occluded: false,
+        }
+    }
+}
+
+/// Light map multiplied over the pixel camera. Lights are queued every frame and drawn together.
+pub struct Lighting {
+    camera: Camera2D,
+    lights: Vec<(Vec2, Light)>,
+}
+impl Lighting {
+    pub fn new() -> Self {
+        Self {
+            camera: create_camera(SCREEN_WIDTH, SCREEN_HEIGHT),
+            lights: Vec::new(),
+        }
+    }
+    pub fn add(&mut self, pos: Vec2, light: Light) {
+        self.lights.push((pos, light));
+    }
+    /// Draws the queued lights into the light map and multiplies it over `pixel_camera`,
+    /// leaving `pixel_camera` set
+    pub fn draw(&mut self, assets: &Assets, world: &World, pixel_camera: &Camera2D) {
+        let view = Rect::new(
+            pixel_camera.target.x - SCREEN_WIDTH / 2.0,
+            pixel_camera.target.y - SCREEN_HEIGHT / 2.0,
+            SCREEN_WIDTH,
+            SCREEN_HEIGHT,
+        );
+        self.camera.target = pixel_camera.target;
+        set_camera(&self.camera);
+        clear_background(world.ambient);
+        for (rect, ambient) in &world.rooms {
+            if view.overlaps(rect) {
+                draw_rectangle(rect.x, rect.y, rect.w, rect.h, *ambient);
+            }
+        }
+        gl_use_material(&assets.additive_material);
+        for (pos, light) in self.lights.drain(..) {
+            let bounds = Rect::new(
+                pos.x - light.radius,
+                pos.y - light.radius,
+                light.radius * 2.0,
+                light.radius * 2.0,
+            );
+            if view.overlaps(&bounds) {
+                draw_light(world, pos, &light);
+            }
+        }
+
+        set_camera(pixel_camera);
+        gl_use_material(&assets.multiply_material);
+        draw_texture(
+            &self.camera.render_target.as_ref().unwrap().texture,
+            view.x,
+            view.y,
+            WHITE,
+        );
+        gl_use_default_material();
+    }
+}
+
+/// Draws a light as a fan of triangles fading out towards its radius, cut short by walls
+fn draw_light(world: &World, pos: Vec2, light: &Light) {
+    let vertex = |pos: Vec2, brightness: f32| {
+        let color = light.color.to_vec() * brightness;
+        Vertex::new(
+            pos.x,
+            pos.y,
+            0.0,
+            0.0,
+            0.0,
+            Color::from_vec(color.with_w(1.0)),
+        )
+    };
+    let mut vertices = vec![vertex(pos, 1.0)];
+    for i in 0..LIGHT_RAYS {
+        let dir = Vec2::from_angle(i as f32 / LIGHT_RAYS as f32 * PI * 2.0);
+        let distance = if light.occluded {
+            let (end, _) = world.raycast(pos, dir, light.radius, |_| None::<()>);
+            (end.distance(pos) + WALL_BLEED).min(light.radius)
+        } else {
+            light.radius
+        };
+        vertices.push(vertex(pos + dir * distance, 1.0 - distance / light.radius));
+    }
+    let mut indices = Vec::with_capacity(LIGHT_RAYS * 3);
+    for i in 0..LIGHT_RAYS as u16 {
+        indices.extend([0, i + 1, (i + 1) % LIGHT_RAYS as u16 + 1]);
+    }
+    draw_mesh(&Mesh {
+        vertices,
+        indices,
+        texture: None,
+    });
+}
@@ -1,13 +1,43 @@
 use macroquad::{miniquad::window::screen_size, prelude::*};
 
-use crate::{assets::*, enemy::*, player::*, utils::*};
+use crate::{
+    assets::*, audio::*, background::Background, damage_number::DamageNumbers, decal::*, enemy::*,
+    hud::HudLayout, input::*, interact::*, lighting::*, minimap::Minimap, particle::*, pickup::*,
+    player::*, rebind::RebindMenu, render::*, save::*, scene::Scene, settings::Settings, utils::*,
+};
 
 mod assets;
+mod audio;
+mod background;
+mod camera;
+mod damage_number;
+mod decal;
 mod enemy;
+mod font;
+mod hud;
+mod input;
+mod interact;
+mod lighting;
+mod menu;
+mod minimap;
+mod modifier;
+mod particle;
+mod pickup;
 mod player;
+mod rebind;
+mod render;
+mod save;
+mod scene;
+mod settings;
+mod terminal;
 mod ui;
 mod utils;
 
+/// Length of a simulation step. Gameplay runs at this rate whatever the frame rate.
+const FIXED_DT: f32 = 1.0 / 120.0;
+/// Longest frame time simulated, so a stall is skipped instead of caught up on step by step
+const MAX_FRAME_TIME: f32 = 0.25;
+
 struct Game<'a> {
     assets: &'a Assets,
     world: World,
@@ -15,13 +45,43 @@ struct Game<'a> {
     pixel_camera: Camera2D,
     world_camera_bg: Camera2D,
     world_camera_fg: Camera2D,
-    stars: StarsBackground,
+    background: Background,
     enemies: Vec<Enemy>,
     projectiles: Vec<Projectile>,
+    particles: Particles,
+    lighting: Lighting,
+    damage_numbers: DamageNumbers,
+    corpses: Vec<Corpse>,
+    decals: Decals,
+    pickups: Vec<Pickup>,
+    render_queue: RenderQueue,
+    minimap: Minimap,
+    interaction: Interaction<InteractTarget>,
+    intensity: CombatIntensity,
+    /// Sounds made since the last frame and where, drained into the audio
+    sfx: Vec<(Sfx, Vec2)>,
+    /// Frame time not simulated yet, less than a step
+    accumulator: f32,
+    last_camera_target: Vec2,
+}
+/// Interactables in the world, as indices into the game's lists
+#[derive(Clone, Copy, PartialEq)]
+enum InteractTarget {
+    Locker(usize),
+    Pickup(usize),
+    Terminal(usize),
+    TileEntity((i16, i16)),
 }
+
 impl<'a> Game<'a> {
-    fn new(assets: &'a Assets) -> Self {
-        let world = World::default();
+    fn new(assets: &'a Assets, state: GameState) -> Self {
+        let GameState {
+            mut player,
+            world,
+            enemies,
+            projectiles,
+            pickups,
+        } = state;
 
         let world_width = ((world.x_max - world.x_min) * 16) as f32 + 16.0 * 16.0;
         let world_height = ((world.y_max - world.y_min) * 16) as f32 + 16.0 * 16.0;
@@ -44,6 +104,10 @@ impl<'a> Game<'a> {
         for chunk in &world.background_details {
             chunk.draw(assets);
         }
+        let decals = Decals::new(
+            &world_camera_bg,
+            vec2(world.x_min as f32, world.y_min as f32) * 16.0,
+        );
         let mut world_camera_fg = create_camera(world_width, world_height);
         world_camera_fg.target = vec2(
             (world.x_min + world.x_max + 16) as f32 / 2.0 * 16.0,
@@ -55,10 +119,14 @@ impl<'a> Game<'a> {
             chunk.draw(assets);
         }
 
-        let pixel_camera = create_camera(SCREEN_WIDTH, SCREEN_HEIGHT);
+        let mut pixel_camera = create_camera(SCREEN_WIDTH, SCREEN_HEIGHT);
 
-        let mut player = Player::new();
-        player.pos = world.get_interactable_spawn(16).unwrap();
+        player.last_pos = player.pos;
+        player.camera.pos = player.pos + 8.0;
+        let last_camera_target = player.camera.target();
+        pixel_camera.target = last_camera_target.floor();
+        let minimap = Minimap::new(&world);
+        let background = Background::new(&world.backdrop, world_camera_bg.target);
 
         Self {
             player,
@@ -67,82 +135,147 @@ impl<'a> Game<'a> {
             pixel_camera,
             world_camera_bg,
             world_camera_fg,
-            enemies: Vec::with_capacity(10), // todo: adjust capcacity later on?
-            stars: StarsBackground::new(),
-            projectiles: Vec::with_capacity(10),
+            enemies,
+            projectiles,
+            particles: Particles::new(),
+            lighting: Lighting::new(),
+            damage_numbers: DamageNumbers::new(),
+            corpses: Vec::new(),
+            decals,
+            background,
+            pickups,
+            render_queue: RenderQueue::new(),
+            minimap,
+            interaction: Interaction::new(),
+            intensity: CombatIntensity::new(),
+            sfx: Vec::new(),
+            accumulator: 0.0,
+            last_camera_target,
         }
     }
-    fn update(&mut self) {
-        // cap delta time to a minimum of 60 fps.
-        let delta_time = get_frame_time().min(1.0 / 60.0);
-        let (actual_screen_width, actual_screen_height) = screen_size();
-        let scale_factor =
-            (actual_screen_width / SCREEN_WIDTH).min(actual_screen_height / SCREEN_HEIGHT);
-        let (mouse_x, mouse_y) = mouse_position();
-        let mouse_x = mouse_x / scale_factor;
-        let mouse_y = mouse_y / scale_factor;
-
+    /// Simulates the steps that fit in the frame and draws the world. The full map doesn't pause it.
+    fn update(&mut self, delta_time: f32, input: &mut Input, settings: &Settings) {
+        if input.take_pressed(Action::Map) {
+            self.minimap.full_map = !self.minimap.full_map;
+        }
+        self.accumulator += delta_time;
+        if self.accumulator < FIXED_DT {
+            input.carry_presses();
+        }
+        while self.accumulator >= FIXED_DT {
+            self.step(FIXED_DT, input, settings);
+            input.consume_presses();
+            self.accumulator -= FIXED_DT;
+        }
+        self.draw_world(delta_time, input, self.accumulator / FIXED_DT);
+    }
+    /// Writes the state that changes while playing, for [`GameState`] to load back
+    fn save(&self) -> String {
+        serialize(
+            &self.player,
+            &self.world,
+            &self.enemies,
+            &self.projectiles,
+            &self.pickups,
+        )
+    }
+    /// Music for the current fight, the first living boss's or else the map's
+    fn music(&self) -> Option<&'static Track> {
+        (self.enemies.iter())
+            .find_map(|enemy| enemy.ty.music)
+            .or(self.world.music)
+    }
+    /// Draws the pixel camera and HUD to the window
+    fn draw(&self, letterbox: &Letterbox, input: &Input, settings: &Settings) {
+        draw_texture_ex(
+            &self.pixel_camera.render_target.as_ref().unwrap().texture,
+            letterbox.offset.x,
+            letterbox.offset.y,
+            WHITE,
+            DrawTextureParams {
+                dest_size: Some(Vec2::new(
+                    SCREEN_WIDTH * letterbox.scale,
+                    SCREEN_HEIGHT * letterbox.scale,
+                )),
+                ..Default::default()
+            },
+        );
+        let interact_label = (input.bindings.get(Action::Interact).first())
+            .map(Binding::label)
+            .unwrap_or_default();
+        let mut layout = HudLayout::new(settings.ui_scale);
+        ui::draw_ui(
+            self.assets,
+            &mut layout,
+            self.interaction.prompt(),
+            &interact_label,
+            &self.player,
+            &self.enemies,
+        );
+        if self.minimap.full_map {
+            self.minimap
+                .draw_full(&self.player, &self.world, &self.enemies);
+        } else {
+            self.minimap
+                .draw(&mut layout, &self.player, &self.world, &self.enemies);
+        }
+    }
+    /// Advances gameplay by one fixed step
+    fn step(&mut self, delta_time: f32, input: &Input, settings: &Settings) {
+        self.last_camera_target = self.player.camera.target();
         self.player.update(
             delta_time,
             &mut self.world,
             &mut self.enemies,
             &mut self.projectiles,
-            (mouse_x, mouse_y),
-        );
-        self.pixel_camera.target = self.player.camera_pos.floor();
-        set_camera(&self.pixel_camera);
-        clear_background(BLACK);
-        self.stars.draw(delta_time, self.player.camera_pos);
-
-        // draw world texture
-        draw_texture_ex(
-            &self.world_camera_bg.render_target.as_ref().unwrap().texture,
-            (self.world.x_min * 16) as f32,
-            (self.world.y_min * 16) as f32,
-            WHITE,
-            DrawTextureParams::default(),
+            &mut self.particles,
+            input,
+            &mut self.sfx,
         );
-        let mut can_take_weapon = false;
-
-        for (locker_pos, slot) in self.world.lockers.iter_mut() {
-            if (self.player.pos + vec2(-8.0, 8.0)).distance_squared(*locker_pos) < 512.0 {
-                draw_texture_ex(
-                    &self.assets.locker.get_at_time(1),
-                    locker_pos.x,
-                    locker_pos.y - 48.0 + 16.0,
-                    WHITE,
-                    DrawTextureParams::default(),
-                );
-                if let Some(weapon) = slot {
-                    can_take_weapon = true;
-                    self.assets.tileset.draw_tile(
-                        locker_pos.x + 8.0,
-                        locker_pos.y - 8.0,
-                        WEAPONS.iter().position(|f| f == weapon).unwrap() as f32,
-                        7.0,
-                        None,
-                    );
-                    if is_key_pressed(KeyCode::E) {
-                        std::mem::swap(&mut self.player.weapon, slot);
-                    }
-                }
-            } else {
-                draw_texture_ex(
-                    &self.assets.locker.get_at_time(0),
-                    locker_pos.x,
-                    locker_pos.y - 48.0 + 16.0,
-                    WHITE,
-                    DrawTextureParams::default(),
-                );
-            }
+        self.minimap.update(&self.player);
+        for locker in &mut self.world.lockers {
+            locker.update(&self.player, &mut self.sfx);
         }
-
-        for ((x, y), entity) in self.world.tile_entities.iter_mut() {
-            let pos = vec2(*x as f32, *y as f32) * 16.0;
-            (entity.draw)(entity, self.assets, pos);
+        for terminal in &mut self.world.terminals {
+            terminal.update(&self.player);
         }
-
-        self.player.draw(self.assets, (mouse_x, mouse_y));
+        let lockers = (self.world.lockers.iter().enumerate()).filter_map(|(i, locker)| {
+            Some((InteractTarget::Locker(i), locker.prompt(&self.player)?))
+        });
+        let pickups = (self.pickups.iter().enumerate()).filter_map(|(i, pickup)| {
+            Some((InteractTarget::Pickup(i), pickup.prompt(&self.player)?))
+        });
+        let terminals = (self.world.terminals.iter().enumerate()).filter_map(|(i, terminal)| {
+            Some((InteractTarget::Terminal(i), terminal.prompt(&self.player)?))
+        });
+        let tile_entities = (self.world.tile_entities.iter()).filter_map(|(pos, entity)| {
+            Some((
+                InteractTarget::TileEntity(*pos),
+                entity.prompt(&self.player)?,
+            ))
+        });
+        let candidates = lockers.chain(pickups).chain(terminals).chain(tile_entities);
+        let target = self
+            .interaction
+            .update(delta_time, candidates, &self.player, input);
+        if let Some(target) = target {
+            let interactable: &mut dyn Interactable = match target {
+                InteractTarget::Locker(i) => &mut self.world.lockers[i],
+                InteractTarget::Pickup(i) => &mut self.pickups[i],
+                InteractTarget::Terminal(i) => &mut self.world.terminals[i],
+                InteractTarget::TileEntity(pos) => self.world.tile_entities.get_mut(&pos).unwrap(),
+            };
+            interactable.interact(&mut self.player, &mut self.sfx);
+        }
+        self.pickups
+            .retain_mut(|pickup| pickup.update(delta_time, &mut self.player, &self.world));
+        self.corpses.retain_mut(|corpse| {
+            if let Some(decal) = corpse.update(delta_time, self.assets) {
+                self.decals.add(decal, corpse.base());
+                return false;
+            }
+            true
+        });
         self.enemies.retain_mut(|enemy| {
             enemy.update(
                 delta_time,
@@ -150,51 +283,354 @@ impl<'a> Game<'a> {
                 &self.world,
                 self.assets,
                 &mut self.projectiles,
+                &mut self.particles,
+                &mut self.sfx,
             );
-            enemy.draw(self.assets);
-            enemy.health > 0.0
+            if enemy.unshown_damage >= 1.0 {
+                if settings.damage_numbers {
+                    let amount = enemy.unshown_damage.floor();
+                    self.damage_numbers
+                        .spawn(amount as u32, enemy.pos - vec2(0.0, 24.0));
+                }
+                enemy.unshown_damage = enemy.unshown_damage.fract();
+            }
+            if enemy.health <= 0.0 {
+                self.particles.emit(&SPLATTER, enemy.pos, Vec2::X);
+                self.decals
+                    .add(DecalKind::splatter(), enemy.pos + vec2(0.0, 10.0));
+                self.corpses.push(Corpse::new(enemy, self.assets));
+                self.pickups
+                    .extend(Pickup::roll(&enemy.ty.drops, enemy.pos - 8.0));
+                self.player.camera.shake(0.15);
+                return false;
+            }
+            true
         });
-
         self.projectiles.retain_mut(|projectile| {
-            projectile.update(
-                self.assets,
+            let keep = projectile.update(
                 &mut self.enemies,
                 &mut self.player,
                 &self.world,
                 delta_time,
-            )
+                &mut self.sfx,
+            );
+            if !keep {
+                self.particles
+                    .emit(&IMPACT, projectile.pos, -projectile.dir);
+            }
+            keep
         });
+        self.intensity.update(delta_time, &self.enemies);
+    }
+    /// Draws the world into the pixel camera, `alpha` of the way from the last step to the current one.
+    /// Effects that don't affect gameplay, like particles, move with the frame instead.
+    fn draw_world(&mut self, delta_time: f32, input: &Input, alpha: f32) {
+        self.decals.update(delta_time, &self.world_camera_bg);
+        let camera_target =
+            interpolate(self.last_camera_target, self.player.camera.target(), alpha);
+        self.pixel_camera.target = camera_target.floor();
+        set_camera(&self.pixel_camera);
+        clear_background(BLACK);
+        self.background.draw(delta_time, camera_target);
+
+        // draw world texture
         draw_texture_ex(
-            &self.world_camera_fg.render_target.as_ref().unwrap().texture,
+            &self.world_camera_bg.render_target.as_ref().unwrap().texture,
             (self.world.x_min * 16) as f32,
             (self.world.y_min * 16) as f32,
             WHITE,
             DrawTextureParams::default(),
         );
-        set_default_camera();
-        clear_background(BLACK);
+
+        // flat tile entities lie on the floor, under everything sorted
+        for ((x, y), entity) in self.world.tile_entities.iter_mut() {
+            let pos = vec2(*x as f32, *y as f32) * 16.0;
+            if entity.tall {
+                self.render_queue
+                    .push(pos.y + 16.0, Drawable::TileEntity((*x, *y)));
+            } else {
+                (entity.draw)(entity, self.assets, pos);
+            }
+        }
+        for (i, locker) in self.world.lockers.iter().enumerate() {
+            self.render_queue
+                .push(locker.pos.y + 16.0, Drawable::Locker(i));
+        }
+        for (i, pickup) in self.pickups.iter().enumerate() {
+            self.render_queue
+                .push(pickup.pos.y + 14.0, Drawable::Pickup(i));
+        }
+        for (i, terminal) in self.world.terminals.iter().enumerate() {
+            self.render_queue
+                .push(terminal.pos.y + 16.0, Drawable::Terminal(i));
+        }
+        for (i, corpse) in self.corpses.iter().enumerate() {
+            self.render_queue.push(corpse.base().y, Drawable::Corpse(i));
+        }
+        for (i, enemy) in self.enemies.iter().enumerate() {
+            self.render_queue
+                .push(enemy.pos.y + 16.0, Drawable::Enemy(i));
+        }
+        self.render_queue
+            .push(self.player.pos.y + 16.0, Drawable::Player);
+        for drawable in self.render_queue.drain() {
+            match drawable {
+                Drawable::Player => self.player.draw(self.assets, alpha),
+                Drawable::Enemy(i) => self.enemies[i].draw(self.assets, alpha),
+                Drawable::Corpse(i) => self.corpses[i].draw(self.assets),
+                Drawable::Pickup(i) => self.pickups[i].draw(self.assets, alpha),
+                Drawable::Locker(i) => self.world.lockers[i].draw(self.assets),
+                Drawable::Terminal(i) => self.world.terminals[i].draw(self.assets),
+                Drawable::TileEntity((x, y)) => {
+                    let entity = self.world.tile_entities.get_mut(&(x, y)).unwrap();
+                    (entity.draw)(entity, self.assets, vec2(x as f32, y as f32) * 16.0);
+                }
+            }
+        }
+
+        for projectile in &self.projectiles {
+            projectile.draw(self.assets, alpha);
+        }
+        self.particles.update(delta_time, self.assets);
         draw_texture_ex(
-            &self.pixel_camera.render_target.as_ref().unwrap().texture,
-            0.0,
-            0.0,
+            &self.world_camera_fg.render_target.as_ref().unwrap().texture,
+            (self.world.x_min * 16) as f32,
+            (self.world.y_min * 16) as f32,
             WHITE,
-            DrawTextureParams {
-                dest_size: Some(Vec2::new(
-                    SCREEN_WIDTH * scale_factor,
-                    SCREEN_HEIGHT * scale_factor,
-                )),
-                ..Default::default()
-            },
+            DrawTextureParams::default(),
         );
-        ui::draw_ui(self.assets, can_take_weapon, &self.player);
+
+        self.lighting
+            .add(self.player.draw_pos(alpha) + 8.0, PLAYER_LIGHT);
+        for (pos, light) in &self.world.lights {
+            self.lighting.add(*pos, *light);
+        }
+        for ((x, y), entity) in &self.world.tile_entities {
+            if entity.enabled
+                && let Some(light) = entity.light
+            {
+                self.lighting
+                    .add(vec2(*x as f32, *y as f32) * 16.0 + 8.0, light);
+            }
+        }
+        for projectile in &self.projectiles {
+            let light = if projectile.friendly {
+                FRIENDLY_PROJECTILE_LIGHT
+            } else {
+                HOSTILE_PROJECTILE_LIGHT
+            };
+            self.lighting.add(projectile.draw_pos(alpha), light);
+        }
+        for enemy in self.enemies.iter().filter(|enemy| !enemy.emerging) {
+            let color = match enemy.status {
+                Some((element, _)) => element.color(),
+                None => ENEMY_GLOW_COLOR,
+            };
+            self.lighting
+                .add(enemy.draw_pos(alpha), Light::new(24.0, color));
+        }
+        self.lighting
+            .draw(self.assets, &self.world, &self.pixel_camera);
+
+        self.damage_numbers.update(delta_time, self.assets);
+        self.player.draw_crosshair(input, alpha);
     }
 }
-#[macroquad::main("space splatter")]
+/// Owns the scene stack and the game being played, if any
+struct App<'a> {
+    assets: &'a Assets,
+    game: Option<Game<'a>>,
+    /// Scenes drawn over each other, only the top one is updated
+    scenes: Vec<Scene>,
+    input: Input,
+    settings: Settings,
+    audio: Audio,
+    /// Size of the window last frame, to notice it being resized
+    screen_size: Vec2,
+}
+impl<'a> App<'a> {
+    fn new(assets: &'a Assets, audio: Audio) -> Self {
+        Self {
+            assets,
+            game: None,
+            scenes: vec![Scene::title()],
+            input: Input::new(Bindings::load()),
+            settings: Settings::load(),
+            audio,
+            screen_size: screen_size().into(),
+        }
+    }
+    /// Returns false once the player quits or closes the window
+    fn update(&mut self) -> bool {
+        if is_quit_requested() {
+            return false;
+        }
+        let size = Vec2::from(screen_size());
+        if size != self.screen_size {
+            self.screen_size = size;
+            // remembered so the window reopens at this size, unless it's just filling the screen
+            if !self.settings.fullscreen {
+                self.settings.window_size = (size.x as u32, size.y as u32);
+            }
+        }
+        let delta_time = get_frame_time().min(MAX_FRAME_TIME);
+        let letterbox = Letterbox::get();
+        match &self.game {
+            // the camera still shows last frame, which is what the cursor is pointing at
+            Some(game) => self
+                .input
+                .update(&letterbox, &game.pixel_camera, game.player.pos + 8.0),
+            None => self
+                .input
+                .update(&letterbox, &Camera2D::default(), Vec2::ZERO),
+        }
+
+        match self.scenes.last_mut().unwrap() {
+            Scene::Title(menu) => match menu.update(&self.input) {
+                Some(0) => self.start(GameState::new()),
+                Some(1) => self.scenes.push(Scene::load_slots()),
+                Some(2) => self.scenes.push(Scene::Settings(RebindMenu::new())),
+                Some(_) => return false,
+                None => {}
+            },
+            Scene::Playing => {
+                let game = self.game.as_mut().unwrap();
+                if self.input.is_pressed(Action::Pause) {
+                    self.scenes.push(Scene::paused());
+                } else {
+                    game.update(delta_time, &mut self.input, &self.settings);
+                    self.audio
+                        .play_sfx(game.sfx.drain(..), game.player.pos + 8.0, &self.settings);
+                    if game.player.health <= 0.0 {
+                        self.scenes.push(Scene::game_over());
+                    }
+                }
+            }
+            Scene::Paused(menu) => {
+                if self.input.is_pressed(Action::Pause) {
+                    self.scenes.pop();
+                } else {
+                    match menu.update(&self.input) {
+                        Some(0) => {
+                            self.scenes.pop();
+                        }
+                        Some(1) => self.scenes.push(Scene::save_slots()),
+                        Some(2) => self.scenes.push(Scene::load_slots()),
+                        Some(3) => self.scenes.push(Scene::Settings(RebindMenu::new())),
+                        Some(_) => self.quit_to_title(),
+                        None => {}
+                    }
+                }
+            }
+            Scene::Settings(menu) => {
+                if !menu.update(&mut self.input, &mut self.settings) {
+                    self.scenes.pop();
+                    if let Err(err) = self.input.bindings.save() {
+                        eprintln!("failed to save bindings: {err}");
+                    }
+                    if let Err(err) = self.settings.save() {
+                        eprintln!("failed to save settings: {err}");
+                    }
+                }
+            }
+            Scene::GameOver(menu) => match menu.update(&self.input) {
+                Some(0) => self.start(GameState::new()),
+                Some(1) => self.scenes.push(Scene::load_slots()),
+                Some(_) => self.quit_to_title(),
+                None => {}
+            },
+            Scene::SaveSlots(menu) => {
+                // the menu stays open when saving fails, to show why
+                let done = match menu.update(&self.input) {
+                    Some(slot) if slot < SAVE_SLOTS => {
+                        let game = self.game.as_ref().unwrap();
+                        let result = save_slot(slot, &game.save());
+                        if let Err(err) = &result {
+                            menu.message = Some(format!("Failed to save: {err}"));
+                        }
+                        result.is_ok()
+                    }
+                    Some(_) => true,
+                    None => false,
+                };
+                if done || self.input.is_pressed(Action::Pause) {
+                    self.scenes.pop();
+                }
+            }
+            Scene::LoadSlots(menu) => match menu.update(&self.input) {
+                Some(slot) if slot < SAVE_SLOTS => {
+                    // empty slots can't be picked
+                    if slot_exists(slot) {
+                        match load_slot(slot) {
+                            Ok(state) => self.start(state),
+                            Err(err) => menu.message = Some(format!("Failed to load: {err}")),
+                        }
+                    }
+                }
+                Some(_) => {
+                    self.scenes.pop();
+                }
+                None => {
+                    if self.input.is_pressed(Action::Pause) {
+                        self.scenes.pop();
+                    }
+                }
+            },
+        }
+        show_mouse(!matches!(self.scenes.last(), Some(Scene::Playing)));
+        let (music, intensity) = match &self.game {
+            Some(game) => (game.music(), game.intensity.level()),
+            None => (Some(&TITLE_MUSIC), 0.0),
+        };
+        self.audio
+            .update_music(delta_time, music, intensity, &self.settings);
+
+        set_default_camera();
+        clear_background(BLACK);
+        if let Some(game) = &self.game {
+            game.draw(&letterbox, &self.input, &self.settings);
+        }
+        match self.scenes.last().unwrap() {
+            Scene::Title(menu)
+            | Scene::Paused(menu)
+            | Scene::GameOver(menu)
+            | Scene::SaveSlots(menu)
+            | Scene::LoadSlots(menu) => {
+                if self.game.is_some() {
+                    let (width, height) = (screen_width(), screen_height());
+                    draw_rectangle(0.0, 0.0, width, height, BLACK.with_alpha(0.6));
+                }
+                menu.draw(self.assets);
+            }
+            Scene::Settings(menu) => menu.draw(self.assets, &self.input, &self.settings),
+            Scene::Playing => {}
+        }
+        true
+    }
+    /// Starts playing from `state`, replacing any game in progress
+    fn start(&mut self, state: GameState) {
+        self.game = Some(Game::new(self.assets, state));
+        self.scenes = vec![Scene::Playing];
+    }
+    fn quit_to_title(&mut self) {
+        self.game = None;
+        self.scenes = vec![Scene::title()];
+    }
+}
+fn window_conf() -> Conf {
+    Settings::load().window_conf()
+}
+#[macroquad::main(window_conf)]
 async fn main() {
     let assets = Assets::default();
-    let mut game = Game::new(&assets);
-    loop {
-        game.update();
+    let audio = Audio::load().await;
+    let mut app = App::new(&assets, audio);
+    // closing the window is handled by `App::update`, so settings are saved on the way out
+    prevent_quit();
+    while app.update() {
         next_frame().await
     }
+    if let Err(err) = app.settings.save() {
+        eprintln!("failed to save settings: {err}");
+    }
 }
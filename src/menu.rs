@@ -0,0 +1,165 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::Assets,
+    font::{Align, TextStyle, draw_pixel_text},
+    input::{Action, Input},
+    utils::*,
+};
+
+/// Colors from `tooltip.ase` and `healthbar.ase`
+pub const MENU_ACCENT: Color = Color::from_hex(0xe76d46);
+pub const MENU_PANEL: Color = Color::from_hex(0x343230);
+
+/// Size of an option, in pixel camera pixels
+const ROW_SIZE: Vec2 = vec2(120.0, 16.0);
+const PADDING: f32 = 4.0;
+
+/// Vertical list of options, picked with the movement and confirm or interact actions or the mouse
+pub struct Menu {
+    title: &'static str,
+    options: Vec<String>,
+    selected: usize,
+    last_mouse: Vec2,
+    /// Shown under the options, like why the last pick failed
+    pub message: Option<String>,
+}
+impl Menu {
+    pub fn new(title: &'static str, options: &[impl AsRef<str>]) -> Self {
+        Self {
+            title,
+            options: options.iter().map(|f| f.as_ref().to_string()).collect(),
+            selected: 0,
+            last_mouse: Vec2::ZERO,
+            message: None,
+        }
+    }
+    /// Rectangle of each option, in window space
+    fn layout(&self, letterbox: &Letterbox) -> Vec<Rect> {
+        let height = self.options.len() as f32 * ROW_SIZE.y;
+        let top_left = (vec2(SCREEN_WIDTH, SCREEN_HEIGHT) - vec2(ROW_SIZE.x, height)) / 2.0;
+        (0..self.options.len())
+            .map(|i| {
+                let pos = letterbox.offset
+                    + (top_left + vec2(0.0, i as f32 * ROW_SIZE.y)).floor() * letterbox.scale;
+                let size = ROW_SIZE * letterbox.scale;
+                Rect::new(pos.x, pos.y, size.x, size.y)
+            })
+            .collect()
+    }
+    /// Returns the index of the option picked this frame
+    pub fn update(&mut self, input: &Input) -> Option<usize> {
+        let count = self.options.len();
+        if input.is_pressed(Action::MoveUp) {
+            self.selected = (self.selected + count - 1) % count;
+        }
+        if input.is_pressed(Action::MoveDown) {
+            self.selected = (self.selected + 1) % count;
+        }
+        let hovered = self
+            .layout(&Letterbox::get())
+            .iter()
+            .position(|row| row.contains(input.screen_mouse));
+        if let Some(hovered) = hovered
+            && input.screen_mouse != self.last_mouse
+        {
+            self.selected = hovered;
+        }
+        self.last_mouse = input.screen_mouse;
+
+        (input.is_pressed(Action::Confirm)
+            || input.is_pressed(Action::Interact)
+            || (hovered.is_some() && is_mouse_button_pressed(MouseButton::Left)))
+        .then_some(self.selected)
+    }
+    pub fn draw(&self, assets: &Assets) {
+        let letterbox = Letterbox::get();
+        let scale = letterbox.scale;
+        let rows = self.layout(&letterbox);
+        let first = rows[0];
+        let last = rows[rows.len() - 1];
+        let padding = PADDING * scale;
+        let panel = Rect::new(
+            first.x - padding,
+            first.y - padding,
+            first.w + padding * 2.0,
+            last.bottom() - first.y + padding * 2.0,
+        );
+        draw_rectangle(
+            panel.x - scale,
+            panel.y - scale,
+            panel.w + scale * 2.0,
+            panel.h + scale * 2.0,
+            BLACK,
+        );
+        draw_rectangle(panel.x, panel.y, panel.w, panel.h, WHITE);
+        draw_rectangle(
+            panel.x + scale,
+            panel.y + scale,
+            panel.w - scale * 2.0,
+            panel.h - scale * 2.0,
+            MENU_PANEL,
+        );
+
+        // whole font pixels, so text stays crisp at any window size
+        let text_scale = scale.floor().max(1.0);
+        let title_style = TextStyle {
+            scale: text_scale * 3.0,
+            align: Align::Center,
+            outline: Some(BLACK),
+            ..Default::default()
+        };
+        let title_height = assets.font.measure(self.title, &title_style).y;
+        draw_pixel_text(
+            &assets.font,
+            self.title,
+            panel.center().x,
+            panel.y - padding * 2.0 - title_height,
+            &title_style,
+        );
+        for (i, (option, row)) in self.options.iter().zip(&rows).enumerate() {
+            let color = if i == self.selected {
+                draw_rectangle(row.x, row.y, row.w, row.h, WHITE);
+                draw_rectangle(
+                    row.x + scale,
+                    row.y + scale,
+                    row.w - scale * 2.0,
+                    row.h - scale * 2.0,
+                    MENU_ACCENT,
+                );
+                WHITE
+            } else {
+                LIGHTGRAY
+            };
+            let style = TextStyle {
+                scale: text_scale * 2.0,
+                color,
+                ..Default::default()
+            };
+            let text_height = assets.font.measure(option, &style).y;
+            draw_pixel_text(
+                &assets.font,
+                option,
+                row.x + padding,
+                row.center().y - text_height / 2.0,
+                &style,
+            );
+        }
+        if let Some(message) = &self.message {
+            let style = TextStyle {
+                scale: text_scale * 2.0,
+                color: MENU_ACCENT,
+                align: Align::Center,
+                outline: Some(BLACK),
+                wrap: Some(SCREEN_WIDTH / 2.0 * scale),
+            };
+            draw_pixel_text(
+                &assets.font,
+                message,
+                panel.center().x,
+                panel.bottom() + padding * 2.0,
+                &style,
+            );
+        }
+    }
+}
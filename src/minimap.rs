@@ -0,0 +1,276 @@
+use std::collections::VecDeque;
+
+use macroquad::{miniquad::window::screen_size, prelude::*};
+
+use crate::{
+    assets::World,
+    enemy::Enemy,
+    hud::{Anchor, HudLayout},
+    player::{Player, get_connected_spawners},
+    ui::PLAYER_HEALTH_COLOR,
+};
+
+/// How far from the player, in tiles, walkable tiles get revealed
+const REVEAL_RADIUS: i16 = 10;
+/// Tiles shown by the HUD minimap
+const MINIMAP_TILES: Vec2 = vec2(36.0, 24.0);
+/// Size of a tile on the HUD minimap, in HUD pixels
+const MINIMAP_TILE_SIZE: f32 = 2.0;
+
+const FLOOR_COLOR: Color = Color::new(0.16, 0.16, 0.22, 1.0);
+const WALL_COLOR: Color = Color::new(0.45, 0.5, 0.6, 1.0);
+const LOCKER_COLOR: Color = Color::from_hex(0xf2d95c);
+const UNCLEARED_COLOR: Color = Color::from_hex(0xd13b3b);
+const CLEARED_COLOR: Color = Color::from_hex(0x7fd14a);
+
+#[derive(Clone, Copy, PartialEq)]
+enum MapTile {
+    Empty,
+    Floor,
+    Wall,
+}
+
+/// Spawners set off by the same trigger
+struct SpawnerGroup {
+    trigger: (i16, i16),
+    spawners: Vec<(i16, i16)>,
+}
+
+/// Map of the station built from the collision and background layers, revealed as the player explores
+pub struct Minimap {
+    /// Tile at the top left of the map
+    origin: (i16, i16),
+    width: usize,
+    height: usize,
+    tiles: Vec<MapTile>,
+    revealed: Vec<bool>,
+    /// Revealed tiles, one pixel each
+    image: Image,
+    texture: Texture2D,
+    spawner_groups: Vec<SpawnerGroup>,
+    last_tile: Option<(i16, i16)>,
+    pub full_map: bool,
+}
+impl Minimap {
+    pub fn new(world: &World) -> Self {
+        let origin = (world.x_min, world.y_min);
+        let width = (world.x_max - world.x_min + 16) as usize;
+        let height = (world.y_max - world.y_min + 16) as usize;
+        let mut tiles = vec![MapTile::Empty; width * height];
+        for (layer, kind) in [
+            (&world.background, MapTile::Floor),
+            (&world.collision, MapTile::Wall),
+        ] {
+            for chunk in layer {
+                for (i, tile) in chunk.tiles.iter().enumerate() {
+                    if *tile == 0 {
+                        continue;
+                    }
+                    let x = (chunk.x - origin.0) as usize + i % 16;
+                    let y = (chunk.y - origin.1) as usize + i / 16;
+                    tiles[x + y * width] = kind;
+                }
+            }
+        }
+
+        let mut spawner_groups: Vec<SpawnerGroup> = Vec::new();
+        for chunk in &world.interactable {
+            for (i, tile) in chunk.tiles.iter().enumerate() {
+                if *tile - 1 != 32 {
+                    continue;
+                }
+                let trigger = (chunk.x + i as i16 % 16, chunk.y + i as i16 / 16);
+                let mut spawners: Vec<(i16, i16)> =
+                    get_connected_spawners(&world.interactable, trigger)
+                        .into_iter()
+                        .filter(|(_, tile)| (96..111).contains(tile))
+                        .map(|(pos, _)| pos)
+                        .collect();
+                spawners.sort();
+                // triggers of the same group all find the same spawners
+                if !spawners.is_empty() && !spawner_groups.iter().any(|f| f.spawners == spawners) {
+                    spawner_groups.push(SpawnerGroup { trigger, spawners });
+                }
+            }
+        }
+
+        let image = Image::gen_image_color(width as u16, height as u16, BLANK);
+        let texture = Texture2D::from_image(&image);
+        texture.set_filter(FilterMode::Nearest);
+        Self {
+            origin,
+            width,
+            height,
+            tiles,
+            revealed: vec![false; width * height],
+            image,
+            texture,
+            spawner_groups,
+            last_tile: None,
+            full_map: false,
+        }
+    }
+    fn index(&self, (x, y): (i16, i16)) -> Option<usize> {
+        let x = usize::try_from(x - self.origin.0).ok()?;
+        let y = usize::try_from(y - self.origin.1).ok()?;
+        (x < self.width && y < self.height).then_some(x + y * self.width)
+    }
+    fn is_revealed(&self, tile: (i16, i16)) -> bool {
+        self.index(tile).is_some_and(|i| self.revealed[i])
+    }
+    /// Reveals the floor reachable from the player without passing through walls, and the walls around it
+    pub fn update(&mut self, player: &Player) {
+        let start = ((player.pos + 8.0) / 16.0).floor();
+        let start = (start.x as i16, start.y as i16);
+        if self.last_tile == Some(start) {
+            return;
+        }
+        self.last_tile = Some(start);
+
+        let mut visited = vec![false; self.width * self.height];
+        let mut queue = VecDeque::from([start]);
+        let mut changed = false;
+        while let Some((x, y)) = queue.pop_front() {
+            let Some(index) = self.index((x, y)) else {
+                continue;
+            };
+            if visited[index] || self.tiles[index] != MapTile::Floor {
+                continue;
+            }
+            visited[index] = true;
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    let neighbor = (x + dx, y + dy);
+                    if let Some(i) = self.index(neighbor)
+                        && !self.revealed[i]
+                        && self.tiles[i] != MapTile::Empty
+                    {
+                        self.revealed[i] = true;
+                        let color = match self.tiles[i] {
+                            MapTile::Wall => WALL_COLOR,
+                            _ => FLOOR_COLOR,
+                        };
+                        self.image.set_pixel(
+                            (i % self.width) as u32,
+                            (i / self.width) as u32,
+                            color,
+                        );
+                        changed = true;
+                    }
+                    let distance = (neighbor.0 - start.0).pow(2) + (neighbor.1 - start.1).pow(2);
+                    if (dx == 0 || dy == 0) && distance <= REVEAL_RADIUS.pow(2) {
+                        queue.push_back(neighbor);
+                    }
+                }
+            }
+        }
+        if changed {
+            self.texture.update(&self.image);
+        }
+    }
+    /// Draws the tiles of `view` with their top left at `pos`, along with the markers in it
+    fn draw_view(
+        &self,
+        view: Rect,
+        pos: Vec2,
+        tile_size: f32,
+        player: &Player,
+        world: &World,
+        enemies: &[Enemy],
+    ) {
+        let bounds = Rect::new(
+            self.origin.0 as f32,
+            self.origin.1 as f32,
+            self.width as f32,
+            self.height as f32,
+        );
+        if let Some(source) = view.intersect(bounds) {
+            draw_texture_ex(
+                &self.texture,
+                pos.x + (source.x - view.x) * tile_size,
+                pos.y + (source.y - view.y) * tile_size,
+                WHITE,
+                DrawTextureParams {
+                    source: Some(source.offset(-bounds.point())),
+                    dest_size: Some(source.size() * tile_size),
+                    ..Default::default()
+                },
+            );
+        }
+        let marker = |tile: Vec2, size: f32, color: Color| {
+            if view.contains(tile) {
+                let at = pos + (tile - view.point()) * tile_size - size / 2.0;
+                draw_rectangle(at.x, at.y, size, size, color);
+            }
+        };
+        for locker in &world.lockers {
+            let tile = (locker.pos / 16.0).floor();
+            if self.is_revealed((tile.x as i16, tile.y as i16)) {
+                marker(tile + 0.5, tile_size * 1.5, LOCKER_COLOR);
+            }
+        }
+        for group in &self.spawner_groups {
+            if !self.is_revealed(group.trigger) {
+                continue;
+            }
+            let cleared = (group.spawners.iter()).all(|f| player.spawned_spawners.contains(f))
+                && !(enemies.iter()).any(|f| {
+                    f.spawner
+                        .is_some_and(|spawner| group.spawners.contains(&spawner))
+                });
+            let color = if cleared {
+                CLEARED_COLOR
+            } else {
+                UNCLEARED_COLOR
+            };
+            let tile = vec2(group.trigger.0 as f32, group.trigger.1 as f32);
+            marker(tile + 0.5, tile_size * 1.5, color);
+        }
+        marker(
+            (player.pos + 8.0) / 16.0,
+            tile_size * 2.0,
+            PLAYER_HEALTH_COLOR,
+        );
+    }
+    /// Draws the minimap in the top right corner of the HUD
+    pub fn draw(&self, layout: &mut HudLayout, player: &Player, world: &World, enemies: &[Enemy]) {
+        let scale = layout.scale;
+        let tile_size = MINIMAP_TILE_SIZE * scale;
+        let size = MINIMAP_TILES * tile_size;
+        // one pixel border around the map
+        let pos = layout.place(Anchor::TopRight, MINIMAP_TILES * MINIMAP_TILE_SIZE + 2.0) + scale;
+        draw_rectangle(
+            pos.x - scale,
+            pos.y - scale,
+            size.x + scale * 2.0,
+            size.y + scale * 2.0,
+            WHITE,
+        );
+        draw_rectangle(pos.x, pos.y, size.x, size.y, BLACK);
+        let center = (player.pos + 8.0) / 16.0;
+        let view = Rect::new(
+            (center.x - MINIMAP_TILES.x / 2.0).floor(),
+            (center.y - MINIMAP_TILES.y / 2.0).floor(),
+            MINIMAP_TILES.x,
+            MINIMAP_TILES.y,
+        );
+        self.draw_view(view, pos, tile_size, player, world, enemies);
+    }
+    /// Draws the whole station over the window
+    pub fn draw_full(&self, player: &Player, world: &World, enemies: &[Enemy]) {
+        let (width, height) = screen_size();
+        draw_rectangle(0.0, 0.0, width, height, BLACK.with_alpha(0.85));
+        let tile_size = ((width * 0.9 / self.width as f32).min(height * 0.9 / self.height as f32))
+            .floor()
+            .max(1.0);
+        let size = vec2(self.width as f32, self.height as f32) * tile_size;
+        let pos = ((vec2(width, height) - size) / 2.0).floor();
+        let view = Rect::new(
+            self.origin.0 as f32,
+            self.origin.1 as f32,
+            self.width as f32,
+            self.height as f32,
+        );
+        self.draw_view(view, pos, tile_size, player, world, enemies);
+    }
+}
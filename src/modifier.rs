@@ -0,0 +1,176 @@
+use macroquad::prelude::*;
+
+use crate::player::{FireMode, Weapon};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Element {
+    /// Deals damage over time
+    Burn,
+    /// Slows movement
+    Cryo,
+}
+impl Element {
+    pub fn color(&self) -> Color {
+        match self {
+            Element::Burn => Color::from_hex(0xf08a3c),
+            Element::Cryo => Color::from_hex(0x8fd3ff),
+        }
+    }
+    /// How long the status lasts after being hit
+    pub fn duration(&self) -> f32 {
+        match self {
+            Element::Burn => 3.0,
+            Element::Cryo => 2.0,
+        }
+    }
+}
+pub const BURN_DAMAGE_PER_SECOND: f32 = 2.0;
+pub const CRYO_SLOWDOWN: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Modifier {
+    Damage,
+    FireRate,
+    ExtraProjectile,
+    Piercing,
+    Elemental(Element),
+}
+pub static MODIFIERS: &[Modifier] = &[
+    Modifier::Damage,
+    Modifier::FireRate,
+    Modifier::ExtraProjectile,
+    Modifier::Piercing,
+    Modifier::Elemental(Element::Burn),
+    Modifier::Elemental(Element::Cryo),
+];
+impl Modifier {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Modifier::Damage => "damage",
+            Modifier::FireRate => "fire rate",
+            Modifier::ExtraProjectile => "extra shot",
+            Modifier::Piercing => "piercing",
+            Modifier::Elemental(Element::Burn) => "burn",
+            Modifier::Elemental(Element::Cryo) => "cryo",
+        }
+    }
+    /// Credits needed to buy the modifier from a locker
+    pub fn price(&self) -> u32 {
+        match self {
+            Modifier::Damage | Modifier::FireRate => 3,
+            Modifier::Piercing | Modifier::Elemental(_) => 4,
+            Modifier::ExtraProjectile => 6,
+        }
+    }
+    pub fn color(&self) -> Color {
+        match self {
+            Modifier::Damage => Color::from_hex(0xd13b3b),
+            Modifier::FireRate => Color::from_hex(0xe8c547),
+            Modifier::ExtraProjectile => Color::from_hex(0x7fd14a),
+            Modifier::Piercing => Color::from_hex(0xc9c9d6),
+            Modifier::Elemental(element) => element.color(),
+        }
+    }
+    /// Draws a 7x7 icon for the modifier, with its top left corner at `x`,`y`
+    pub fn draw_icon(&self, x: f32, y: f32, scale: f32) {
+        let color = self.color();
+        let pixel = |px: f32, py: f32, w: f32, h: f32| {
+            draw_rectangle(x + px * scale, y + py * scale, w * scale, h * scale, color)
+        };
+        draw_rectangle(x, y, 7.0 * scale, 7.0 * scale, BLACK);
+        match self {
+            Modifier::Damage => {
+                // plus
+                pixel(3.0, 1.0, 1.0, 5.0);
+                pixel(1.0, 3.0, 5.0, 1.0);
+            }
+            Modifier::FireRate => {
+                // double chevron
+                for offset in [1.0, 3.0] {
+                    pixel(offset, 2.0, 1.0, 3.0);
+                    pixel(offset + 1.0, 3.0, 1.0, 1.0);
+                }
+            }
+            Modifier::ExtraProjectile => {
+                // three dots
+                pixel(1.0, 3.0, 1.0, 1.0);
+                pixel(3.0, 1.0, 1.0, 1.0);
+                pixel(3.0, 5.0, 1.0, 1.0);
+                pixel(5.0, 3.0, 1.0, 1.0);
+            }
+            Modifier::Piercing => {
+                // arrow
+                pixel(1.0, 3.0, 5.0, 1.0);
+                pixel(4.0, 2.0, 1.0, 3.0);
+            }
+            Modifier::Elemental(_) => {
+                // droplet
+                pixel(3.0, 1.0, 1.0, 1.0);
+                pixel(2.0, 2.0, 3.0, 2.0);
+                pixel(1.0, 4.0, 5.0, 2.0);
+            }
+        }
+    }
+}
+
+/// A weapon held by the player or stored in the world, along with the modifiers applied to it
+#[derive(Clone, PartialEq, Debug)]
+pub struct WeaponInstance {
+    pub weapon: &'static Weapon,
+    pub modifiers: Vec<Modifier>,
+}
+impl WeaponInstance {
+    pub const fn new(weapon: &'static Weapon) -> Self {
+        Self {
+            weapon,
+            modifiers: Vec::new(),
+        }
+    }
+    fn count(&self, modifier: Modifier) -> i32 {
+        self.modifiers.iter().filter(|f| **f == modifier).count() as i32
+    }
+    pub fn damage_multiplier(&self) -> f32 {
+        1.25_f32.powi(self.count(Modifier::Damage))
+    }
+    pub fn attack_delay(&self) -> f32 {
+        self.weapon.attack_delay * 0.8_f32.powi(self.count(Modifier::FireRate))
+    }
+    /// Amount of projectiles per shot, and the angle they are spread over
+    pub fn multishot(&self) -> (u8, f32) {
+        let extra = self.count(Modifier::ExtraProjectile) as u8;
+        let (amount, angle) = self.weapon.multishot.unwrap_or((1, 0.0));
+        let per_angle = if amount > 1 {
+            angle / amount as f32
+        } else {
+            0.15
+        };
+        (amount + extra, angle + per_angle * extra as f32)
+    }
+    /// Amount of enemies a projectile can pass through
+    pub fn pierce(&self) -> u32 {
+        self.count(Modifier::Piercing) as u32
+    }
+    /// Element of the weapon. The most recently applied element wins.
+    pub fn element(&self) -> Option<Element> {
+        self.modifiers.iter().rev().find_map(|f| match f {
+            Modifier::Elemental(element) => Some(*element),
+            _ => None,
+        })
+    }
+    /// Whether applying the modifier would change anything
+    pub fn accepts(&self, modifier: Modifier) -> bool {
+        match modifier {
+            Modifier::ExtraProjectile => !matches!(self.weapon.fire_mode, FireMode::Beam { .. }),
+            Modifier::Piercing => !matches!(self.weapon.fire_mode, FireMode::Beam { .. }),
+            Modifier::Elemental(element) => self.element() != Some(element),
+            _ => true,
+        }
+    }
+}
+
+/// Draws a row of modifier icons, used for tooltips and the HUD
+pub fn draw_modifier_row(modifiers: &[Modifier], x: f32, y: f32, scale: f32) {
+    for (i, modifier) in modifiers.iter().enumerate() {
+        modifier.draw_icon(x + i as f32 * 8.0 * scale, y, scale);
+    }
+}
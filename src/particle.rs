@@ -0,0 +1,187 @@
+use std::f32::consts::PI;
+
+use macroquad::prelude::*;
+
+use crate::assets::Assets;
+
+/// Particles past this amount aren't spawned
+const MAX_PARTICLES: usize = 1024;
+
+pub enum ParticleShape {
+    /// Square of the particle's size
+    Pixel,
+    /// Frame of a projectile animation, scaled to the particle's size
+    Sprite(usize),
+}
+
+/// Describes a burst of particles
+pub struct Emitter {
+    pub amount: (u32, u32),
+    pub lifetime: (f32, f32),
+    pub speed: (f32, f32),
+    /// Angle the particles are spread over, centered on the emit direction
+    pub spread: f32,
+    /// How quickly particles slow down
+    pub drag: f32,
+    pub size: (f32, f32),
+    /// Colors the particle fades through over its lifetime
+    pub colors: &'static [Color],
+    pub shape: ParticleShape,
+}
+impl Emitter {
+    fn color_at(&self, progress: f32) -> Color {
+        let max = (self.colors.len() - 1) as f32;
+        let index = (progress * max).min(max);
+        let from = self.colors[index.floor() as usize];
+        let to = self.colors[index.ceil() as usize];
+        Color::from_vec(from.to_vec().lerp(to.to_vec(), index.fract()))
+    }
+}
+
+pub static IMPACT: Emitter = Emitter {
+    amount: (3, 5),
+    lifetime: (0.1, 0.25),
+    speed: (30.0, 90.0),
+    spread: PI,
+    drag: 8.0,
+    size: (1.0, 1.0),
+    colors: &[WHITE, Color::from_hex(0x87d1ef), Color::from_hex(0x3b7bd1)],
+    shape: ParticleShape::Pixel,
+};
+pub static SPLATTER: Emitter = Emitter {
+    amount: (12, 18),
+    lifetime: (0.3, 0.7),
+    speed: (20.0, 120.0),
+    spread: PI * 2.0,
+    drag: 6.0,
+    size: (1.0, 3.0),
+    colors: &[
+        Color::from_hex(0xa8e05f),
+        Color::from_hex(0x5fa83a),
+        Color::from_hex(0x2e5c2a),
+    ],
+    shape: ParticleShape::Pixel,
+};
+pub static MUZZLE_FLASH: Emitter = Emitter {
+    amount: (1, 1),
+    lifetime: (0.05, 0.05),
+    speed: (0.0, 0.0),
+    spread: 0.0,
+    drag: 0.0,
+    size: (10.0, 12.0),
+    colors: &[WHITE, Color::from_hex(0x87d1ef)],
+    shape: ParticleShape::Sprite(2),
+};
+pub static EMERGE_DIRT: Emitter = Emitter {
+    amount: (1, 2),
+    lifetime: (0.2, 0.4),
+    speed: (20.0, 50.0),
+    spread: PI * 0.8,
+    drag: 4.0,
+    size: (1.0, 2.0),
+    colors: &[Color::from_hex(0x5a5a6e), Color::from_hex(0x2b2b38)],
+    shape: ParticleShape::Pixel,
+};
+pub static THRUSTER: Emitter = Emitter {
+    amount: (1, 2),
+    lifetime: (0.1, 0.2),
+    speed: (40.0, 80.0),
+    spread: PI * 0.3,
+    drag: 3.0,
+    size: (1.0, 2.0),
+    colors: &[
+        Color::from_hex(0xf2d95c),
+        Color::from_hex(0xf08a3c),
+        Color::from_hex(0x5a2a2a),
+    ],
+    shape: ParticleShape::Pixel,
+};
+
+struct Particle {
+    emitter: &'static Emitter,
+    pos: Vec2,
+    velocity: Vec2,
+    time: f32,
+    lifetime: f32,
+    size: f32,
+}
+
+/// Fixed pool of particles, drawn in the pixel camera. Slots are allocated up front and reused once their particle dies.
+pub struct Particles {
+    slots: Vec<Particle>,
+    /// Indices of the slots without a live particle
+    free: Vec<usize>,
+}
+impl Particles {
+    pub fn new() -> Self {
+        // a particle is dead once its time reaches its lifetime
+        let dead = || Particle {
+            emitter: &IMPACT,
+            pos: Vec2::ZERO,
+            velocity: Vec2::ZERO,
+            time: 0.0,
+            lifetime: 0.0,
+            size: 0.0,
+        };
+        Self {
+            slots: (0..MAX_PARTICLES).map(|_| dead()).collect(),
+            free: (0..MAX_PARTICLES).rev().collect(),
+        }
+    }
+    /// Spawns a burst of particles at `pos`, moving in `dir`
+    pub fn emit(&mut self, emitter: &'static Emitter, pos: Vec2, dir: Vec2) {
+        let amount = rand::gen_range(emitter.amount.0, emitter.amount.1 + 1);
+        for _ in 0..amount {
+            let Some(index) = self.free.pop() else {
+                return;
+            };
+            let angle =
+                dir.to_angle() + rand::gen_range(-emitter.spread / 2.0, emitter.spread / 2.0);
+            self.slots[index] = Particle {
+                emitter,
+                pos,
+                velocity: Vec2::from_angle(angle)
+                    * rand::gen_range(emitter.speed.0, emitter.speed.1),
+                time: 0.0,
+                lifetime: rand::gen_range(emitter.lifetime.0, emitter.lifetime.1),
+                size: rand::gen_range(emitter.size.0, emitter.size.1).round(),
+            };
+        }
+    }
+    pub fn update(&mut self, delta_time: f32, assets: &Assets) {
+        for (i, particle) in self.slots.iter_mut().enumerate() {
+            if particle.time >= particle.lifetime {
+                continue;
+            }
+            particle.time += delta_time;
+            if particle.time >= particle.lifetime {
+                self.free.push(i);
+                continue;
+            }
+            particle.pos += particle.velocity * delta_time;
+            particle.velocity = particle
+                .velocity
+                .lerp(Vec2::ZERO, particle.emitter.drag * delta_time);
+            let color = particle.emitter.color_at(particle.time / particle.lifetime);
+            match particle.emitter.shape {
+                ParticleShape::Pixel => draw_rectangle(
+                    particle.pos.x.floor(),
+                    particle.pos.y.floor(),
+                    particle.size,
+                    particle.size,
+                    color,
+                ),
+                ParticleShape::Sprite(animation_index) => draw_texture_ex(
+                    assets.projectiles.animations[animation_index].get_at_time(0),
+                    (particle.pos.x - particle.size / 2.0).floor(),
+                    (particle.pos.y - particle.size / 2.0).floor(),
+                    color,
+                    DrawTextureParams {
+                        dest_size: Some(vec2(particle.size, particle.size)),
+                        ..Default::default()
+                    },
+                ),
+            }
+        }
+    }
+}
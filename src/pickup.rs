@@ -0,0 +1,361 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{Assets, World},
+    audio::Sfx,
+    interact::{Interactable, Prompt},
+    modifier::{Modifier, WeaponInstance, draw_modifier_row},
+    player::{Player, update_physicsbody},
+    utils::interpolate,
+};
+
+pub const MAX_ARMOR: f32 = 50.0;
+pub const MAX_AMMO: u32 = 60;
+
+/// Distance at which pickups start flying towards the player
+const MAGNET_RADIUS: f32 = 40.0;
+/// Distance at which pickups are collected
+const COLLECT_RADIUS: f32 = 8.0;
+/// Distance at which a weapon pickup can be swapped with the held weapon
+const WEAPON_REACH: f32 = 20.0;
+/// Distance from a locker's center at which it opens
+const LOCKER_REACH: f32 = 22.0;
+/// Seconds interact has to be held to buy a modifier, so credits aren't spent by accident
+const BUY_HOLD_TIME: f32 = 0.4;
+const MAGNET_SPEED: f32 = 220.0;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum PickupKind {
+    Health(f32),
+    Armor(f32),
+    Ammo(u32),
+    Credits(u32),
+    Weapon(WeaponInstance),
+    /// Applied to the held weapon when collected
+    Modifier(Modifier),
+}
+impl PickupKind {
+    /// Gets the pickup placed by a tile in the interactable layer
+    pub fn from_tile(tile: i16) -> Option<Self> {
+        match tile {
+            128 => Some(HEALTH_PACK),
+            129 => Some(ARMOR_SHARD),
+            130 => Some(AMMO_BOX),
+            _ => None,
+        }
+    }
+    /// Whether the player would benefit from picking this up right now
+    fn is_useful(&self, player: &Player) -> bool {
+        match self {
+            PickupKind::Health(_) => player.health < 100.0,
+            PickupKind::Armor(_) => player.armor < MAX_ARMOR,
+            PickupKind::Ammo(_) => player.ammo < MAX_AMMO,
+            PickupKind::Credits(_) => true,
+            PickupKind::Weapon(_) => player.weapon.is_none(),
+            PickupKind::Modifier(modifier) => player
+                .weapon
+                .as_ref()
+                .is_some_and(|instance| instance.accepts(*modifier)),
+        }
+    }
+    fn apply(&self, player: &mut Player) {
+        match self {
+            PickupKind::Health(amount) => player.health = (player.health + amount).min(100.0),
+            PickupKind::Armor(amount) => player.armor = (player.armor + amount).min(MAX_ARMOR),
+            PickupKind::Ammo(amount) => player.ammo = (player.ammo + amount).min(MAX_AMMO),
+            PickupKind::Credits(amount) => player.credits += amount,
+            PickupKind::Weapon(weapon) => {
+                player.equip(weapon.clone());
+            }
+            PickupKind::Modifier(modifier) => {
+                if let Some(instance) = &mut player.weapon {
+                    instance.modifiers.push(*modifier);
+                }
+            }
+        }
+    }
+}
+
+pub const HEALTH_PACK: PickupKind = PickupKind::Health(25.0);
+pub const HEALTH_SHARD: PickupKind = PickupKind::Health(5.0);
+pub const ARMOR_SHARD: PickupKind = PickupKind::Armor(10.0);
+pub const AMMO_BOX: PickupKind = PickupKind::Ammo(12);
+pub const CREDIT: PickupKind = PickupKind::Credits(1);
+
+pub const HEALTH_COLOR: Color = Color::from_hex(0xd13b3b);
+pub const ARMOR_COLOR: Color = Color::from_hex(0x3b7bd1);
+pub const AMMO_COLOR: Color = Color::from_hex(0xe8c547);
+pub const CREDITS_COLOR: Color = Color::from_hex(0xf2d95c);
+
+/// Possible drops of an enemy, as `(chance, kind)`. At most one entry is dropped, rolled in order.
+pub type DropTable = Vec<(f32, PickupKind)>;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum LockerItem {
+    /// Swapped with the held weapon for free
+    Weapon(WeaponInstance),
+    /// Bought for credits and applied to the held weapon
+    Modifier(Modifier),
+}
+impl LockerItem {
+    /// Takes the item out of the locker if the player is able to. Weapons are swapped with the held weapon.
+    pub fn take(slot: &mut Option<LockerItem>, player: &mut Player) {
+        match slot.take() {
+            Some(LockerItem::Weapon(weapon)) => {
+                *slot = player.equip(weapon).map(LockerItem::Weapon);
+            }
+            Some(LockerItem::Modifier(modifier)) => {
+                if let Some(instance) = &mut player.weapon
+                    && instance.accepts(modifier)
+                    && player.credits >= modifier.price()
+                {
+                    player.credits -= modifier.price();
+                    instance.modifiers.push(modifier);
+                } else {
+                    *slot = Some(LockerItem::Modifier(modifier));
+                }
+            }
+            None => {}
+        }
+    }
+    /// Draws the item as displayed inside an open locker
+    pub fn draw(&self, assets: &Assets, x: f32, y: f32) {
+        match self {
+            LockerItem::Weapon(instance) => {
+                assets
+                    .tileset
+                    .draw_tile(x, y, instance.weapon.sprite as f32, 7.0, None);
+            }
+            LockerItem::Modifier(modifier) => modifier.draw_icon(x + 4.0, y + 5.0, 1.0),
+        }
+    }
+    /// Draws a small panel next to the locker, listing a weapon's modifiers or a modifier's price
+    pub fn draw_tooltip(&self, x: f32, y: f32) {
+        let (modifiers, price) = match self {
+            LockerItem::Weapon(instance) => (instance.modifiers.as_slice(), 0),
+            LockerItem::Modifier(modifier) => (std::slice::from_ref(modifier), modifier.price()),
+        };
+        if modifiers.is_empty() {
+            return;
+        }
+        let width = (modifiers.len().max(price as usize) as f32 * 8.0).max(8.0) + 3.0;
+        let height = if price > 0 { 15.0 } else { 10.0 };
+        draw_rectangle(x - 1.0, y - 1.0, width + 2.0, height + 2.0, WHITE);
+        draw_rectangle(x, y, width, height, BLACK);
+        draw_modifier_row(modifiers, x + 2.0, y + 2.0, 1.0);
+        for i in 0..price {
+            draw_rectangle(x + 3.0 + i as f32 * 8.0, y + 10.0, 3.0, 3.0, CREDITS_COLOR);
+        }
+    }
+}
+
+pub struct Locker {
+    /// Top left of the locker's bottom tile
+    pub pos: Vec2,
+    pub item: Option<LockerItem>,
+    /// Whether the player was in reach last frame
+    pub open: bool,
+}
+impl Locker {
+    fn center(&self) -> Vec2 {
+        self.pos + vec2(16.0, 0.0)
+    }
+    fn in_reach(&self, player: &Player) -> bool {
+        (player.pos + 8.0).distance(self.center()) < LOCKER_REACH
+    }
+    /// Opens the locker while the player is in reach
+    pub fn update(&mut self, player: &Player, sfx: &mut Vec<(Sfx, Vec2)>) {
+        let in_reach = self.in_reach(player);
+        if in_reach && !self.open {
+            sfx.push((Sfx::LockerOpen, self.center()));
+        }
+        self.open = in_reach;
+    }
+    /// Draws the locker, with its item on display while open
+    pub fn draw(&self, assets: &Assets) {
+        draw_texture(
+            assets.locker.get_at_time(if self.open { 1 } else { 0 }),
+            self.pos.x,
+            self.pos.y - 48.0 + 16.0,
+            WHITE,
+        );
+        if self.open
+            && let Some(item) = &self.item
+        {
+            item.draw(assets, self.pos.x + 8.0, self.pos.y - 8.0);
+            item.draw_tooltip(self.pos.x + 33.0, self.pos.y - 30.0);
+        }
+    }
+}
+impl Interactable for Locker {
+    fn prompt(&self, player: &Player) -> Option<Prompt> {
+        let (text, hold) = match self.item.as_ref()? {
+            LockerItem::Weapon(_) if player.weapon.is_some() => ("Swap weapon".to_string(), None),
+            LockerItem::Weapon(_) => ("Take weapon".to_string(), None),
+            LockerItem::Modifier(modifier) => (
+                format!("Buy {} ({})", modifier.name(), modifier.price()),
+                Some(BUY_HOLD_TIME),
+            ),
+        };
+        Some(Prompt {
+            text,
+            pos: self.center(),
+            radius: LOCKER_REACH,
+            priority: 0,
+            hold,
+        })
+    }
+    fn interact(&mut self, player: &mut Player, _sfx: &mut Vec<(Sfx, Vec2)>) {
+        LockerItem::take(&mut self.item, player);
+    }
+}
+
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub pos: Vec2,
+    pub last_pos: Vec2,
+    pub velocity: Vec2,
+    pub time: f32,
+}
+impl Pickup {
+    pub fn new(kind: PickupKind, pos: Vec2) -> Self {
+        Self {
+            kind,
+            pos,
+            last_pos: pos,
+            velocity: Vec2::ZERO,
+            time: rand::gen_range(0.0, 10.0),
+        }
+    }
+    /// Rolls a drop table, returning the dropped pickup (if any) flung out from `pos`
+    pub fn roll(table: &DropTable, pos: Vec2) -> Option<Self> {
+        let (_, kind) = table
+            .iter()
+            .find(|(chance, _)| rand::gen_range(0.0, 1.0) < *chance)?;
+        let mut pickup = Pickup::new(kind.clone(), pos);
+        pickup.velocity = Vec2::from_angle(rand::gen_range(0.0, std::f32::consts::TAU)) * 90.0;
+        Some(pickup)
+    }
+    fn center(&self) -> Vec2 {
+        self.pos + 8.0
+    }
+    /// Returns false once the pickup has been collected
+    pub fn update(&mut self, delta_time: f32, player: &mut Player, world: &World) -> bool {
+        self.last_pos = self.pos;
+        self.time += delta_time;
+        let to_player = (player.pos + 8.0) - self.center();
+        let distance = to_player.length();
+
+        if self.kind.is_useful(player) {
+            if distance < COLLECT_RADIUS {
+                self.kind.apply(player);
+                return false;
+            }
+            if distance < MAGNET_RADIUS {
+                let pull = 1.0 - distance / MAGNET_RADIUS;
+                self.velocity = to_player / distance * MAGNET_SPEED * (0.3 + pull);
+                self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
+                return true;
+            }
+        }
+        if self.velocity.length_squared() > 0.1 {
+            self.pos = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
+            self.velocity = self.velocity.lerp(Vec2::ZERO, 6.0 * delta_time);
+        }
+        true
+    }
+    pub fn draw(&self, assets: &Assets, alpha: f32) {
+        let pos = interpolate(self.last_pos, self.pos, alpha);
+        let bob = ((self.time * 4.0).sin() * 2.0).round();
+        let x = pos.x.floor();
+        let y = pos.y.floor();
+        draw_ellipse(
+            x + 8.0,
+            y + 14.0,
+            4.0 - bob * 0.5,
+            1.5,
+            0.0,
+            BLACK.with_alpha(0.4),
+        );
+        let y = y + bob - 2.0;
+        match &self.kind {
+            PickupKind::Health(amount) => {
+                let amount = *amount;
+                let size = if amount >= 10.0 { 9.0 } else { 5.0 };
+                let offset = (16.0 - size) / 2.0;
+                draw_rectangle(x + offset, y + offset, size, size, WHITE);
+                let arm = (size / 3.0).floor();
+                draw_rectangle(
+                    x + offset + arm,
+                    y + offset + 1.0,
+                    size - arm * 2.0,
+                    size - 2.0,
+                    HEALTH_COLOR,
+                );
+                draw_rectangle(
+                    x + offset + 1.0,
+                    y + offset + arm,
+                    size - 2.0,
+                    size - arm * 2.0,
+                    HEALTH_COLOR,
+                );
+            }
+            PickupKind::Armor(_) => {
+                draw_triangle(
+                    vec2(x + 8.0, y + 3.0),
+                    vec2(x + 4.0, y + 8.0),
+                    vec2(x + 12.0, y + 8.0),
+                    ARMOR_COLOR,
+                );
+                draw_triangle(
+                    vec2(x + 8.0, y + 13.0),
+                    vec2(x + 4.0, y + 8.0),
+                    vec2(x + 12.0, y + 8.0),
+                    ARMOR_COLOR.with_alpha(0.7),
+                );
+                draw_rectangle(x + 7.0, y + 5.0, 1.0, 2.0, WHITE);
+            }
+            PickupKind::Ammo(_) => {
+                draw_rectangle(x + 4.0, y + 6.0, 8.0, 6.0, BLACK);
+                draw_rectangle(x + 5.0, y + 7.0, 6.0, 4.0, AMMO_COLOR);
+                draw_rectangle(x + 5.0, y + 4.0, 1.0, 3.0, AMMO_COLOR);
+                draw_rectangle(x + 8.0, y + 4.0, 1.0, 3.0, AMMO_COLOR);
+            }
+            PickupKind::Credits(_) => {
+                draw_rectangle(x + 6.0, y + 5.0, 4.0, 6.0, BLACK);
+                draw_rectangle(x + 5.0, y + 6.0, 6.0, 4.0, BLACK);
+                draw_rectangle(x + 6.0, y + 6.0, 4.0, 4.0, CREDITS_COLOR);
+                draw_rectangle(x + 7.0, y + 7.0, 1.0, 2.0, WHITE);
+            }
+            PickupKind::Weapon(instance) => {
+                assets
+                    .tileset
+                    .draw_tile(x, y, instance.weapon.sprite as f32, 7.0, None);
+            }
+            PickupKind::Modifier(modifier) => modifier.draw_icon(x + 4.0, y + 4.0, 1.0),
+        }
+    }
+}
+/// Weapons on the floor are swapped with the held weapon. Other pickups are collected by walking over them.
+impl Interactable for Pickup {
+    fn prompt(&self, player: &Player) -> Option<Prompt> {
+        if !matches!(self.kind, PickupKind::Weapon(_)) || player.weapon.is_none() {
+            return None;
+        }
+        Some(Prompt {
+            text: "Swap weapon".to_string(),
+            pos: self.center(),
+            radius: WEAPON_REACH,
+            priority: 1,
+            hold: None,
+        })
+    }
+    fn interact(&mut self, player: &mut Player, _sfx: &mut Vec<(Sfx, Vec2)>) {
+        if player.weapon.is_some()
+            && let PickupKind::Weapon(weapon) = &mut self.kind
+            && let Some(held) = player.equip(weapon.clone())
+        {
+            *weapon = held;
+        }
+    }
+}
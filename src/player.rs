@@ -4,8 +4,13 @@ use macroquad::prelude::*;
 
 use crate::{
     assets::{Assets, BARRIER, Chunk, World},
-    enemy::{ENEMIES, Enemy},
-    utils::*,
+    audio::Sfx,
+    camera::GameCamera,
+    enemy::{ENEMIES, Enemy, HIT_FLASH_TIME},
+    input::{Action, AimSource, GAMEPAD_CROSSHAIR_DISTANCE, Input},
+    modifier::{Element, WeaponInstance},
+    particle::{MUZZLE_FLASH, Particles, THRUSTER},
+    utils::interpolate,
 };
 
 fn tile_to_chunk(pos: (i16, i16)) -> (i16, i16) {
@@ -20,7 +25,8 @@ fn vec2_to_tile(pos: Vec2) -> (i16, i16) {
     (cx, cy)
 }
 
-fn get_connected_spawners(chunks: &[Chunk], start: (i16, i16)) -> Vec<((i16, i16), i16)> {
+/// Gets every interactable tile connected to `start`, except triggers
+pub fn get_connected_spawners(chunks: &[Chunk], start: (i16, i16)) -> Vec<((i16, i16), i16)> {
     fn recurse(
         chunks: &[Chunk],
         start: (i16, i16),
@@ -49,7 +55,7 @@ fn get_connected_spawners(chunks: &[Chunk], start: (i16, i16)) -> Vec<((i16, i16
     result
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Debug)]
 pub struct ProjectileType {
     pub animation_index: usize,
     pub speed: f32,
@@ -58,34 +64,69 @@ pub struct ProjectileType {
 pub struct Projectile {
     pub ty: &'static ProjectileType,
     pub pos: Vec2,
+    pub last_pos: Vec2,
     pub dir: Vec2,
     pub time: f32,
     pub friendly: bool,
+    /// Multiplier for size and damage, used by charged shots
+    pub scale: f32,
+    pub damage_multiplier: f32,
+    /// Amount of enemies the projectile can still pass through
+    pub pierce: u32,
+    pub element: Option<Element>,
+    /// Ids of enemies already hit, so piercing projectiles only hit each once
+    pub hit: Vec<u32>,
 }
 impl Projectile {
+    pub fn new(ty: &'static ProjectileType, pos: Vec2, dir: Vec2, friendly: bool) -> Self {
+        Self {
+            ty,
+            pos,
+            last_pos: pos,
+            dir,
+            time: 0.0,
+            friendly,
+            scale: 1.0,
+            damage_multiplier: 1.0,
+            pierce: 0,
+            element: None,
+            hit: Vec::new(),
+        }
+    }
     pub fn update(
         &mut self,
-        assets: &Assets,
         enemies: &mut [Enemy],
         player: &mut Player,
         world: &World,
         delta_time: f32,
+        sfx: &mut Vec<(Sfx, Vec2)>,
     ) -> bool {
+        self.last_pos = self.pos;
         self.pos += self.dir * self.ty.speed * delta_time;
+        let hit_radius = 16.0 * self.scale;
+        let damage = self.ty.damage * self.scale * self.damage_multiplier;
 
         if self.friendly {
-            if let Some(enemy) = enemies
-                .iter_mut()
-                .find(|enemy| enemy.pos.distance_squared(self.pos) < 256.0)
-            {
+            if let Some(enemy) = enemies.iter_mut().find(|enemy| {
+                enemy.pos.distance_squared(self.pos) < hit_radius * hit_radius
+                    && !self.hit.contains(&enemy.id)
+            }) {
                 if enemy.emerging {
                     return false;
                 }
-                enemy.health -= self.ty.damage;
-                return false;
+                enemy.damage(damage);
+                sfx.push((Sfx::Hit, enemy.pos));
+                if let Some(element) = self.element {
+                    enemy.apply_element(element);
+                }
+                if self.pierce == 0 {
+                    return false;
+                }
+                self.pierce -= 1;
+                self.hit.push(enemy.id);
             }
-        } else if player.pos.distance_squared(self.pos) < 256.0 {
-            player.health -= self.ty.damage;
+        } else if player.pos.distance_squared(self.pos) < hit_radius * hit_radius {
+            player.damage(damage, sfx);
             return false;
         }
 
@@ -97,18 +138,26 @@ impl Projectile {
         {
             return false;
         }
+        true
+    }
+    pub fn draw_pos(&self, alpha: f32) -> Vec2 {
+        interpolate(self.last_pos, self.pos, alpha)
+    }
+    pub fn draw(&self, assets: &Assets, alpha: f32) {
+        let pos = self.draw_pos(alpha);
+        let size = (16.0 * self.scale).floor();
         draw_texture_ex(
             assets.projectiles.animations[self.ty.animation_index]
                 .get_at_time((self.time * 1000.0) as u32),
-            self.pos.x.floor() - 8.0,
-            self.pos.y.floor() - 8.0,
+            pos.x.floor() - size / 2.0,
+            pos.y.floor() - size / 2.0,
             WHITE,
             DrawTextureParams {
                 rotation: self.dir.to_angle(),
+                dest_size: Some(vec2(size, size)),
                 ..Default::default()
             },
         );
-        true
     }
 }
 
@@ -127,67 +176,281 @@ pub static ALIEN_BALL: ProjectileType = ProjectileType {
     speed: 100.0,
     damage: 4.0,
 };
-#[derive(PartialEq)]
+pub static PROJECTILES: &[&ProjectileType] = &[&ENERGY_BALL, &ENERGY_SHOT, &ALIEN_BALL];
+#[derive(PartialEq, Debug)]
+pub enum FireMode {
+    Automatic,
+    SemiAuto,
+    /// Charges while held and fires on release, scaling the projectile up to `max_scale` after `max_time` seconds
+    Charge {
+        max_time: f32,
+        max_scale: f32,
+    },
+    /// Hitscan ray that deals `damage` every `attack_delay` while held
+    Beam {
+        range: f32,
+        damage: f32,
+    },
+}
+#[derive(PartialEq, Debug)]
 pub struct Weapon {
     pub projectile: &'static ProjectileType,
     pub attack_delay: f32,
     pub multishot: Option<(u8, f32)>,
+    /// Ammo used per shot. Weapons with no cost can always fire.
+    pub ammo_cost: u32,
+    pub fire_mode: FireMode,
+    /// Column of the weapon's sprite in the tileset's weapon row
+    pub sprite: usize,
+    pub spread: Spread,
+    /// How hard each shot pushes the player and camera back
+    pub recoil: f32,
+    /// Played for each shot, or each beam tick
+    pub sound: Sfx,
+}
+/// Accuracy of a weapon. All angles are in radians and cover the full cone.
+#[derive(PartialEq, Debug)]
+pub struct Spread {
+    /// Spread of the first shot
+    pub base: f32,
+    pub per_shot: f32,
+    pub max: f32,
+    /// Added spread recovered per second
+    pub recovery: f32,
 }
 pub static GUN: Weapon = Weapon {
     projectile: &ENERGY_BALL,
     attack_delay: 1.0 / 3.0,
     multishot: None,
+    ammo_cost: 0,
+    fire_mode: FireMode::Automatic,
+    sprite: 0,
+    spread: Spread {
+        base: 0.03,
+        per_shot: 0.08,
+        max: 0.25,
+        recovery: 0.6,
+    },
+    recoil: 20.0,
+    sound: Sfx::Gun,
 };
 pub static RIFLE: Weapon = Weapon {
     projectile: &ENERGY_SHOT,
     attack_delay: 1.0 / 7.0,
     multishot: None,
+    ammo_cost: 0,
+    fire_mode: FireMode::Automatic,
+    sprite: 1,
+    spread: Spread {
+        base: 0.05,
+        per_shot: 0.05,
+        max: 0.35,
+        recovery: 0.8,
+    },
+    recoil: 15.0,
+    sound: Sfx::Rifle,
 };
 pub static SHOTGUN: Weapon = Weapon {
     projectile: &ENERGY_BALL,
     attack_delay: 0.6,
     multishot: Some((3, PI / 5.0)),
+    ammo_cost: 1,
+    fire_mode: FireMode::SemiAuto,
+    sprite: 2,
+    spread: Spread {
+        base: 0.12,
+        per_shot: 0.15,
+        max: 0.3,
+        recovery: 0.5,
+    },
+    recoil: 90.0,
+    sound: Sfx::Shotgun,
+};
+pub static LASER: Weapon = Weapon {
+    projectile: &ENERGY_SHOT,
+    attack_delay: 0.1,
+    multishot: None,
+    ammo_cost: 0,
+    fire_mode: FireMode::Beam {
+        range: 120.0,
+        damage: 0.6,
+    },
+    sprite: 3,
+    spread: Spread {
+        base: 0.0,
+        per_shot: 0.0,
+        max: 0.0,
+        recovery: 0.0,
+    },
+    recoil: 0.0,
+    sound: Sfx::Laser,
 };
-pub static WEAPONS: &[&Weapon] = &[&GUN, &RIFLE, &SHOTGUN];
+pub static CANNON: Weapon = Weapon {
+    projectile: &ENERGY_BALL,
+    attack_delay: 0.5,
+    multishot: None,
+    ammo_cost: 1,
+    fire_mode: FireMode::Charge {
+        max_time: 1.0,
+        max_scale: 2.5,
+    },
+    sprite: 4,
+    spread: Spread {
+        base: 0.02,
+        per_shot: 0.1,
+        max: 0.2,
+        recovery: 0.6,
+    },
+    recoil: 40.0,
+    sound: Sfx::Cannon,
+};
+pub static WEAPONS: &[&Weapon] = &[&GUN, &RIFLE, &SHOTGUN, &LASER, &CANNON];
+pub const BEAM_COLOR: Color = Color::from_hex(0x7be8f0);
+
+pub struct MeleeAttack {
+    pub damage: f32,
+    /// Reach of the swing, measured from the player's center
+    pub range: f32,
+    /// Total angle covered by the swing, in radians
+    pub arc: f32,
+    pub knockback: f32,
+    pub cooldown: f32,
+    /// How long the swing is shown for
+    pub duration: f32,
+}
+pub static SWING: MeleeAttack = MeleeAttack {
+    damage: 4.0,
+    range: 26.0,
+    arc: PI * 0.6,
+    knockback: 220.0,
+    cooldown: 0.45,
+    duration: 0.15,
+};
+pub const MELEE_COLOR: Color = Color::from_hex(0xdff6f5);
 
 pub struct Player {
-    pub weapon: Option<&'static Weapon>,
+    pub weapon: Option<WeaponInstance>,
     pub pos: Vec2,
-    pub camera_pos: Vec2,
+    pub last_pos: Vec2,
+    pub camera: GameCamera,
     pub velocity: Vec2,
     pub animation_time: f32,
     pub walking: bool,
     pub moving_left: bool,
     pub health: f32,
+    pub armor: f32,
+    pub ammo: u32,
+    pub credits: u32,
     pub spawned_spawners: Vec<(i16, i16)>,
     pub attack_counter: f32,
+    pub melee_counter: f32,
+    pub swing_time: f32,
+    pub swing_dir: Vec2,
+    /// Time the trigger has been held for with a charge weapon
+    pub charge: f32,
+    pub beam_end: Option<Vec2>,
+    pub aim: Vec2,
+    /// Extra spread built up from firing
+    pub bloom: f32,
+    pub dash_time: f32,
+    pub dash_counter: f32,
+    pub flash_time: f32,
 }
 impl Player {
     pub fn new() -> Self {
         Self {
             weapon: None,
             pos: Vec2::ZERO,
-            camera_pos: Vec2::ZERO,
+            last_pos: Vec2::ZERO,
+            camera: GameCamera::new(Vec2::ZERO),
             velocity: Vec2::ZERO,
             animation_time: 0.0,
             walking: false,
             moving_left: false,
             health: 100.0,
+            armor: 0.0,
+            ammo: 24,
+            credits: 0,
             spawned_spawners: Vec::new(),
             attack_counter: 0.0,
+            melee_counter: 0.0,
+            swing_time: 0.0,
+            swing_dir: Vec2::X,
+            charge: 0.0,
+            beam_end: None,
+            aim: Vec2::X,
+            bloom: 0.0,
+            dash_time: 0.0,
+            dash_counter: 0.0,
+            flash_time: 0.0,
         }
     }
+    /// Switches to `weapon`, dropping any charge or beam from the old one, and returns the old one
+    pub fn equip(&mut self, weapon: WeaponInstance) -> Option<WeaponInstance> {
+        self.charge = 0.0;
+        self.beam_end = None;
+        self.weapon.replace(weapon)
+    }
+    /// Deals damage to the player, with armor absorbing part of it
+    pub fn damage(&mut self, amount: f32, sfx: &mut Vec<(Sfx, Vec2)>) {
+        let absorbed = (amount * ARMOR_ABSORPTION).min(self.armor);
+        self.armor -= absorbed;
+        self.health -= amount - absorbed;
+        self.camera.shake(amount / 40.0);
+        self.flash_time = HIT_FLASH_TIME;
+        sfx.push((Sfx::PlayerHurt, self.pos + 8.0));
+    }
+    /// Swings at everything within the melee arc, hitting enemies and deflecting hostile projectiles
+    fn melee(
+        &mut self,
+        attack: &MeleeAttack,
+        aim: Vec2,
+        enemies: &mut [Enemy],
+        projectiles: &mut [Projectile],
+        sfx: &mut Vec<(Sfx, Vec2)>,
+    ) {
+        self.melee_counter = attack.cooldown;
+        self.swing_time = attack.duration;
+        self.swing_dir = aim;
+        let center = self.pos + 8.0;
+        sfx.push((Sfx::Swing, center));
+        let in_arc = |pos: Vec2| {
+            let delta = pos - center;
+            delta.length() < attack.range
+                && (delta.length() < 8.0 || delta.angle_between(aim).abs() < attack.arc / 2.0)
+        };
+        for enemy in enemies.iter_mut() {
+            if enemy.emerging || !in_arc(enemy.pos) {
+                continue;
+            }
+            enemy.damage(attack.damage);
+            sfx.push((Sfx::Hit, enemy.pos));
+            enemy.knockback = (enemy.pos - center).normalize_or(aim) * attack.knockback;
+        }
+        for projectile in projectiles.iter_mut() {
+            if projectile.friendly || !in_arc(projectile.pos) {
+                continue;
+            }
+            projectile.friendly = true;
+            projectile.dir = aim;
+        }
+    }
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         delta_time: f32,
         world: &mut World,
         enemies: &mut Vec<Enemy>,
         projectiles: &mut Vec<Projectile>,
-        mouse: (f32, f32),
+        particles: &mut Particles,
+        input: &Input,
+        sfx: &mut Vec<(Sfx, Vec2)>,
     ) {
+        self.last_pos = self.pos;
         self.animation_time += delta_time;
+        self.flash_time -= delta_time;
         self.walking = false;
-        let axis = get_input_axis();
+        let axis = input.movement;
         if axis.length() > 0.0 {
             self.walking = true;
             if axis.x < 0.0 {
@@ -195,40 +458,99 @@ impl Player {
             } else if axis.x > 0.0 {
                 self.moving_left = false;
             }
-            self.velocity += axis.normalize() * delta_time * 3600.0;
+            self.velocity += axis * delta_time * 3600.0;
         }
-        self.attack_counter -= delta_time;
-        if self.attack_counter <= 0.0
-            && let Some(weapon) = self.weapon
-            && is_mouse_button_down(MouseButton::Left)
+        self.dash_time -= delta_time;
+        self.dash_counter -= delta_time;
+        if self.dash_counter <= 0.0 && input.is_pressed(Action::Dash) {
+            self.dash_time = DASH_DURATION;
+            self.dash_counter = DASH_COOLDOWN;
+            self.velocity = axis.normalize_or(input.aim) * DASH_SPEED;
+        }
+        let aim = input.aim;
+        self.swing_time -= delta_time;
+        self.melee_counter -= delta_time;
+        if self.melee_counter <= 0.0
+            && (input.is_down(Action::Melee)
+                || (self.weapon.is_none() && input.is_down(Action::Fire)))
         {
-            self.attack_counter = weapon.attack_delay;
-            let mut new = Vec::new();
-            let multishot = weapon.multishot.unwrap_or((1, 0.0));
-            let per_angle = multishot.1 / multishot.0 as f32;
-            for i in 0..multishot.0 {
-                let angle = (vec2(mouse.0, mouse.1)
-                    - vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0))
-                .normalize()
-                .to_angle()
-                    + i as f32 * per_angle
-                    - per_angle * multishot.0 as f32 / 2.0;
-
-                new.push(Projectile {
-                    ty: weapon.projectile,
-                    time: 0.0,
-                    pos: self.pos + 8.0,
-                    dir: Vec2::from_angle(angle),
-                    friendly: true,
-                });
+            self.melee(&SWING, aim, enemies, projectiles, sfx);
+        }
+        self.aim = aim;
+        if let Some(instance) = &self.weapon {
+            self.bloom = (self.bloom - instance.weapon.spread.recovery * delta_time).max(0.0);
+        }
+        self.attack_counter -= delta_time;
+        self.beam_end = None;
+        if let Some(weapon) = self.weapon.as_ref().map(|f| f.weapon) {
+            let ready = self.attack_counter <= 0.0 && self.ammo >= weapon.ammo_cost;
+            let held = input.is_down(Action::Fire);
+            match weapon.fire_mode {
+                FireMode::Automatic => {
+                    if ready && held {
+                        self.fire(1.0, projectiles, particles, sfx);
+                    }
+                }
+                FireMode::SemiAuto => {
+                    if ready && input.is_pressed(Action::Fire) {
+                        self.fire(1.0, projectiles, particles, sfx);
+                    }
+                }
+                FireMode::Charge {
+                    max_time,
+                    max_scale,
+                } => {
+                    if held && (ready || self.charge > 0.0) {
+                        self.charge += delta_time;
+                    } else if self.charge > 0.0 {
+                        let amount = (self.charge / max_time).min(1.0);
+                        self.fire(
+                            1.0 + (max_scale - 1.0) * amount,
+                            projectiles,
+                            particles,
+                            sfx,
+                        );
+                        self.charge = 0.0;
+                    }
+                }
+                FireMode::Beam { range, damage } => {
+                    if held && self.ammo >= weapon.ammo_cost {
+                        let start = self.pos + 8.0;
+                        let (end, hit) = world.raycast(start, aim, range, |pos| {
+                            enemies.iter().position(|enemy| {
+                                !enemy.emerging && enemy.pos.distance_squared(pos) < 100.0
+                            })
+                        });
+                        self.beam_end = Some(end);
+                        if self.attack_counter <= 0.0
+                            && let Some(instance) = &self.weapon
+                        {
+                            self.attack_counter = instance.attack_delay();
+                            self.ammo -= weapon.ammo_cost;
+                            sfx.push((weapon.sound, start));
+                            if let Some(index) = hit {
+                                enemies[index].damage(damage * instance.damage_multiplier());
+                                sfx.push((Sfx::Hit, enemies[index].pos));
+                                if let Some(element) = instance.element() {
+                                    enemies[index].apply_element(element);
+                                }
+                            }
+                        }
+                    }
+                }
             }
-            projectiles.append(&mut new);
+        } else {
+            self.charge = 0.0;
+        }
+        if self.dash_time > 0.0 {
+            particles.emit(&THRUSTER, self.pos + vec2(8.0, 10.0), -self.velocity);
+        } else {
+            let friction = if axis.length() == 0.0 { 20.0 } else { 10.0 } * delta_time;
+            self.velocity = self
+                .velocity
+                .clamp_length_max(2.0 * 70.0)
+                .lerp(Vec2::ZERO, friction);
         }
-        let friction = if axis.length() == 0.0 { 20.0 } else { 10.0 } * delta_time;
-        self.velocity = self
-            .velocity
-            .clamp_length_max(2.0 * 70.0)
-            .lerp(Vec2::ZERO, friction);
         let new = update_physicsbody(self.pos, &mut self.velocity, delta_time, world);
         self.walking &= self.velocity.length_squared() > 0.1;
         self.pos = new;
@@ -251,59 +573,186 @@ impl Player {
                 match tile {
                     96..111 => {
                         new_spawned.push((x, y));
-                        let enemy = Enemy::new(
+                        let mut enemy = Enemy::new(
                             &ENEMIES[tile as usize - 96],
                             vec2(x as f32 * 16.0, y as f32 * 16.0),
                         );
+                        enemy.spawner = Some((x, y));
+                        sfx.push((Sfx::Emerge, enemy.pos));
                         new_enemies.push(enemy);
                     }
-                    64 => {
-                        if enemies.is_empty() && self.weapon.is_some() {
-                            tile_entities.retain(|p, _| p != &(x, y));
-                        }
+                    64 if enemies.is_empty()
+                        && self.weapon.is_some()
+                        && tile_entities.remove(&(x, y)).is_some() =>
+                    {
+                        sfx.push((Sfx::BarrierOpen, vec2(x as f32, y as f32) * 16.0 + 8.0));
                     }
                     82 | 83 => {
-                        tile_entities.insert((x, y), BARRIER.instantiate(tile));
+                        tile_entities.insert((x, y), BARRIER.instantiate(tile, (x, y)));
+                        sfx.push((Sfx::BarrierClose, vec2(x as f32, y as f32) * 16.0 + 8.0));
                     }
-                    _ => panic!(),
+                    // pickups, lights and terminals are placed once when the world loads
+                    _ => {}
                 }
             }
         }
         enemies.append(&mut new_enemies);
         std::mem::swap(&mut tile_entities, &mut world.tile_entities);
         self.spawned_spawners.append(&mut new_spawned);
-        self.camera_pos = self.pos
+        self.camera
+            .update(delta_time, self.pos + 8.0, input.look, world);
+    }
+    /// Spawns the weapon's projectiles in the aim direction
+    fn fire(
+        &mut self,
+        scale: f32,
+        projectiles: &mut Vec<Projectile>,
+        particles: &mut Particles,
+        sfx: &mut Vec<(Sfx, Vec2)>,
+    ) {
+        let Some(instance) = &self.weapon else {
+            return;
+        };
+        let weapon = instance.weapon;
+        self.attack_counter = instance.attack_delay();
+        self.ammo -= weapon.ammo_cost;
+        let multishot = instance.multishot();
+        let per_angle = multishot.1 / multishot.0 as f32;
+        let spread = self.current_spread(weapon) / 2.0;
+        for i in 0..multishot.0 {
+            let angle = self.aim.to_angle() + i as f32 * per_angle
+                - per_angle * multishot.0 as f32 / 2.0
+                + rand::gen_range(-spread, spread);
+
+            let mut projectile = Projectile::new(
+                weapon.projectile,
+                self.pos + 8.0,
+                Vec2::from_angle(angle),
+                true,
+            );
+            projectile.scale = scale;
+            projectile.damage_multiplier = instance.damage_multiplier();
+            projectile.pierce = instance.pierce();
+            projectile.element = instance.element();
+            projectiles.push(projectile);
+        }
+        particles.emit(&MUZZLE_FLASH, self.pos + 8.0 + self.aim * 10.0, self.aim);
+        sfx.push((weapon.sound, self.pos + 8.0));
+        self.bloom = (self.bloom + weapon.spread.per_shot).min(weapon.spread.max);
+        let recoil = weapon.recoil * scale;
+        self.velocity -= self.aim * recoil;
+        self.camera.kick -= self.aim * recoil / 20.0;
+    }
+    fn current_spread(&self, weapon: &Weapon) -> f32 {
+        weapon.spread.base + self.bloom
+    }
+    /// Draws the crosshair at the cursor, or in front of the player when aiming with a gamepad,
+    /// with a gap showing the current spread
+    pub fn draw_crosshair(&self, input: &Input, alpha: f32) {
+        let center = self.draw_pos(alpha) + 8.0;
+        let pos = match input.aim_source {
+            AimSource::Mouse => input.mouse,
+            AimSource::Gamepad => center + self.aim * GAMEPAD_CROSSHAIR_DISTANCE,
+        };
+        let pos = pos.floor() + 0.5;
+        let gap = match &self.weapon {
+            Some(instance) => {
+                let distance = pos.distance(center);
+                (distance * (self.current_spread(instance.weapon) / 2.0).tan()).clamp(2.0, 24.0)
+            }
+            None => 2.0,
+        }
+        .floor();
+        for dir in [Vec2::X, Vec2::Y, -Vec2::X, -Vec2::Y] {
+            let start = pos + dir * gap;
+            let end = pos + dir * (gap + 3.0);
+            draw_line(start.x, start.y, end.x, end.y, 1.0, WHITE);
+        }
     }
-    pub fn draw(&self, assets: &Assets, mouse: (f32, f32)) {
+    pub fn draw_pos(&self, alpha: f32) -> Vec2 {
+        interpolate(self.last_pos, self.pos, alpha)
+    }
+    pub fn draw(&self, assets: &Assets, alpha: f32) {
+        let pos = self.draw_pos(alpha);
+        if self.flash_time > 0.0 {
+            gl_use_material(&assets.flash_material);
+        }
         draw_texture_ex(
             assets.player.animations[if self.walking { 1 } else { 0 }]
                 .get_at_time((self.animation_time * 1000.0) as u32),
-            self.pos.x.floor(),
-            self.pos.y.floor(),
+            pos.x.floor(),
+            pos.y.floor(),
             WHITE,
             DrawTextureParams {
-                flip_x: mouse.0 < SCREEN_WIDTH / 2.0,
+                flip_x: self.aim.x < 0.0,
                 ..Default::default()
             },
         );
-        if let Some(weapon) = &self.weapon {
+        gl_use_default_material();
+        if self.swing_time > 0.0 {
+            let progress = 1.0 - self.swing_time / SWING.duration;
+            let arc = SWING.arc.to_degrees();
+            draw_arc(
+                pos.x.floor() + 8.0,
+                pos.y.floor() + 8.0,
+                12,
+                SWING.range - 6.0,
+                self.swing_dir.to_angle().to_degrees() - arc / 2.0,
+                2.0 + 3.0 * (1.0 - progress),
+                arc,
+                MELEE_COLOR.with_alpha(1.0 - progress * 0.6),
+            );
+        }
+        let muzzle = pos.floor() + 8.0 + self.aim * 12.0;
+        if let Some(end) = self.beam_end {
+            let flicker = rand::gen_range(0.0, 1.0);
+            draw_line(
+                muzzle.x,
+                muzzle.y,
+                end.x.floor(),
+                end.y.floor(),
+                3.0 + flicker,
+                BEAM_COLOR.with_alpha(0.5),
+            );
+            draw_line(muzzle.x, muzzle.y, end.x.floor(), end.y.floor(), 1.0, WHITE);
+            draw_circle(end.x.floor(), end.y.floor(), 2.0 + flicker, BEAM_COLOR);
+        }
+        if self.charge > 0.0
+            && let Some(instance) = &self.weapon
+            && let FireMode::Charge { max_time, .. } = instance.weapon.fire_mode
+        {
+            let amount = (self.charge / max_time).min(1.0);
+            let full = amount >= 1.0 && ((self.charge * 12.0) as u32).is_multiple_of(2);
+            draw_circle(
+                muzzle.x,
+                muzzle.y,
+                (1.0 + amount * 4.0).floor(),
+                if full { WHITE } else { BEAM_COLOR },
+            );
+        }
+        if let Some(instance) = &self.weapon {
             assets.tileset.draw_tile(
-                self.pos.x.floor() + 7.0,
-                self.pos.y.floor(),
-                WEAPONS.iter().position(|f| f == weapon).unwrap() as f32,
+                pos.x.floor() + 7.0,
+                pos.y.floor(),
+                instance.weapon.sprite as f32,
                 7.0,
                 Some(&DrawTextureParams {
-                    rotation: (vec2(mouse.0, mouse.1)
-                        - vec2(SCREEN_WIDTH / 2.0, SCREEN_HEIGHT / 2.0))
-                    .to_angle(),
-                    flip_y: mouse.0 < SCREEN_WIDTH / 2.0,
-                    pivot: Some(self.pos.floor() + 8.0),
+                    rotation: self.aim.to_angle(),
+                    flip_y: self.aim.x < 0.0,
+                    pivot: Some(pos.floor() + 8.0),
                     ..Default::default()
                 }),
             );
         }
     }
 }
+/// Fraction of incoming damage absorbed by armor
+const ARMOR_ABSORPTION: f32 = 2.0 / 3.0;
+/// Furthest a body moves between collision checks, less than a tile
+const MAX_PHYSICS_MOVE: f32 = 8.0;
+const DASH_SPEED: f32 = 360.0;
+const DASH_DURATION: f32 = 0.12;
+const DASH_COOLDOWN: f32 = 0.6;
 fn ceil_g(a: f32) -> f32 {
     if a < 0.0 { a.floor() } else { a.ceil() }
 }
@@ -322,7 +771,23 @@ fn get_tile<T: Borrow<Chunk>>(chunks: &[T], x: i16, y: i16) -> i16 {
     chunk.tile_at(local_x as _, local_y as _).unwrap_or(0)
 }
 
-pub fn update_physicsbody(pos: Vec2, velocity: &mut Vec2, delta_time: f32, world: &World) -> Vec2 {
+/// Moves a body by its velocity, stopping it at walls and closed barriers.
+/// Long moves are split up so fast bodies can't pass through walls.
+pub fn update_physicsbody(
+    mut pos: Vec2,
+    velocity: &mut Vec2,
+    delta_time: f32,
+    world: &World,
+) -> Vec2 {
+    let steps = (velocity.length() * delta_time / MAX_PHYSICS_MOVE)
+        .ceil()
+        .max(1.0);
+    for _ in 0..steps as u32 {
+        pos = move_physicsbody(pos, velocity, delta_time / steps, world);
+    }
+    pos
+}
+fn move_physicsbody(pos: Vec2, velocity: &mut Vec2, delta_time: f32, world: &World) -> Vec2 {
     let mut new = pos + *velocity * delta_time;
 
     let tile_x = pos.x / 16.0;
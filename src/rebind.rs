@@ -0,0 +1,269 @@
+use macroquad::{miniquad::window::screen_size, prelude::*};
+
+use crate::{
+    assets::Assets,
+    font::{Align, TextStyle, draw_pixel_text},
+    input::{ACTIONS, Action, Binding, Input},
+    settings::{Settings, UI_SCALES, VOLUMES, WINDOW_SIZES},
+};
+
+/// A row after the actions, cycling a setting through its values
+struct SettingRow {
+    name: &'static str,
+    value: fn(&Settings) -> String,
+    cycle: fn(&mut Settings),
+}
+static SETTING_ROWS: &[SettingRow] = &[
+    SettingRow {
+        name: "Damage numbers",
+        value: |settings| String::from(if settings.damage_numbers { "On" } else { "Off" }),
+        cycle: |settings| settings.damage_numbers = !settings.damage_numbers,
+    },
+    SettingRow {
+        name: "UI scale",
+        value: |settings| format!("{}x", settings.ui_scale),
+        cycle: |settings| {
+            let index = UI_SCALES.iter().position(|f| *f == settings.ui_scale);
+            settings.ui_scale = UI_SCALES[index.map_or(0, |i| (i + 1) % UI_SCALES.len())];
+        },
+    },
+    SettingRow {
+        name: "Master volume",
+        value: |settings| format_volume(settings.master_volume),
+        cycle: |settings| settings.master_volume = next_volume(settings.master_volume),
+    },
+    SettingRow {
+        name: "Effects volume",
+        value: |settings| format_volume(settings.sfx_volume),
+        cycle: |settings| settings.sfx_volume = next_volume(settings.sfx_volume),
+    },
+    SettingRow {
+        name: "Music volume",
+        value: |settings| format_volume(settings.music_volume),
+        cycle: |settings| settings.music_volume = next_volume(settings.music_volume),
+    },
+    SettingRow {
+        name: "Fullscreen",
+        value: |settings| String::from(if settings.fullscreen { "On" } else { "Off" }),
+        cycle: |settings| {
+            settings.fullscreen = !settings.fullscreen;
+            settings.apply_window();
+        },
+    },
+    SettingRow {
+        name: "Window size",
+        value: |settings| format!("{}x{}", settings.window_size.0, settings.window_size.1),
+        cycle: |settings| {
+            let index = WINDOW_SIZES.iter().position(|f| *f == settings.window_size);
+            settings.window_size = WINDOW_SIZES[index.map_or(0, |i| (i + 1) % WINDOW_SIZES.len())];
+            settings.apply_window();
+        },
+    },
+];
+fn format_volume(volume: f32) -> String {
+    format!("{}%", (volume * 100.0).round())
+}
+fn next_volume(volume: f32) -> f32 {
+    let index = VOLUMES.iter().position(|f| *f == volume);
+    VOLUMES[index.map_or(0, |i| (i + 1) % VOLUMES.len())]
+}
+
+/// Menu listing every action, letting the player bind a new key or mouse button to it,
+/// followed by rows cycling the settings
+pub struct RebindMenu {
+    selected: usize,
+    /// Which of the selected action's bindings gets changed, one past the last adds a binding
+    slot: usize,
+    /// Whether the next key or mouse press replaces the selected binding
+    waiting: bool,
+    last_mouse: Vec2,
+}
+impl RebindMenu {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            slot: 0,
+            waiting: false,
+            last_mouse: Vec2::ZERO,
+        }
+    }
+    /// Font size and rectangle of each row, in window space
+    fn layout() -> (f32, Vec<Rect>) {
+        let (width, height) = screen_size();
+        let font_size = (height / 32.0).floor().max(16.0);
+        let row_count = ACTIONS.len() + SETTING_ROWS.len();
+        let row_height = font_size * 1.2;
+        let x = width / 2.0 - font_size * 8.0;
+        // the rows and the gap between actions and settings, centered
+        let y = (height - (row_count + 1) as f32 * row_height) / 2.0;
+        let rows = (0..row_count)
+            .map(|i| {
+                // gap between the actions and settings
+                let gap = if i >= ACTIONS.len() { row_height } else { 0.0 };
+                Rect::new(
+                    x,
+                    y + i as f32 * row_height + gap,
+                    font_size * 16.0,
+                    row_height,
+                )
+            })
+            .collect();
+        (font_size, rows)
+    }
+    /// Returns false once the menu has been closed
+    pub fn update(&mut self, input: &mut Input, settings: &mut Settings) -> bool {
+        if self.waiting {
+            // pause cancels instead of being bound
+            if input.is_pressed(Action::Pause) {
+                self.waiting = false;
+            } else if let Some(binding) = Binding::get_pressed() {
+                let bindings = (input.bindings.map)
+                    .entry(ACTIONS[self.selected])
+                    .or_default();
+                bindings.retain(|f| *f != binding);
+                if self.slot < bindings.len() {
+                    bindings[self.slot] = binding;
+                } else {
+                    bindings.push(binding);
+                }
+                self.waiting = false;
+            }
+            return true;
+        }
+        if input.is_pressed(Action::Pause) {
+            return false;
+        }
+        let row_count = ACTIONS.len() + SETTING_ROWS.len();
+        if input.is_pressed(Action::MoveUp) {
+            self.selected = (self.selected + row_count - 1) % row_count;
+        }
+        if input.is_pressed(Action::MoveDown) {
+            self.selected = (self.selected + 1) % row_count;
+        }
+        if let Some(action) = ACTIONS.get(self.selected) {
+            let slots = input.bindings.get(*action).len() + 1;
+            self.slot = self.slot.min(slots - 1);
+            if input.is_pressed(Action::MoveLeft) {
+                self.slot = (self.slot + slots - 1) % slots;
+            }
+            if input.is_pressed(Action::MoveRight) {
+                self.slot = (self.slot + 1) % slots;
+            }
+        }
+        let hovered = Self::layout()
+            .1
+            .iter()
+            .position(|row| row.contains(input.screen_mouse));
+        if let Some(hovered) = hovered
+            && input.screen_mouse != self.last_mouse
+        {
+            self.selected = hovered;
+        }
+        self.last_mouse = input.screen_mouse;
+
+        if input.is_pressed(Action::Confirm)
+            || input.is_pressed(Action::Interact)
+            || (hovered.is_some() && is_mouse_button_pressed(MouseButton::Left))
+        {
+            match SETTING_ROWS.get(self.selected.wrapping_sub(ACTIONS.len())) {
+                Some(row) => (row.cycle)(settings),
+                None => self.waiting = true,
+            }
+        }
+        if input.is_pressed(Action::ResetBinding)
+            && let Some(action) = ACTIONS.get(self.selected)
+        {
+            input.bindings.reset(*action);
+        }
+        true
+    }
+    pub fn draw(&self, assets: &Assets, input: &Input, settings: &Settings) {
+        let (width, height) = screen_size();
+        draw_rectangle(0.0, 0.0, width, height, BLACK.with_alpha(0.7));
+
+        let (font_size, rows) = Self::layout();
+        // whole font pixels, about as tall as the font size
+        let scale = (font_size / 8.0).floor().max(1.0);
+        let title_style = TextStyle {
+            scale: scale * 1.5,
+            ..Default::default()
+        };
+        let title_height = assets.font.measure("Controls", &title_style).y;
+        draw_pixel_text(
+            &assets.font,
+            "Controls",
+            rows[0].x,
+            rows[0].y - font_size - title_height,
+            &title_style,
+        );
+        let labels = ACTIONS
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let mut bindings: Vec<String> = input
+                    .bindings
+                    .get(*action)
+                    .iter()
+                    .map(Binding::label)
+                    .collect();
+                // brackets mark the binding that gets changed
+                if i == self.selected {
+                    match bindings.get_mut(self.slot) {
+                        Some(label) => *label = format!("[{label}]"),
+                        None => bindings.push(String::from("[+]")),
+                    }
+                }
+                (action.name(), bindings.join(" / "))
+            })
+            .chain((SETTING_ROWS.iter()).map(|row| (row.name, (row.value)(settings))));
+        for (i, ((name, value), row)) in labels.zip(&rows).enumerate() {
+            let color = if i == self.selected { YELLOW } else { WHITE };
+            let value = if i == self.selected && self.waiting {
+                String::from("press a key...")
+            } else {
+                value
+            };
+            let style = TextStyle {
+                scale,
+                color,
+                ..Default::default()
+            };
+            let top = row.center().y - assets.font.measure(name, &style).y / 2.0;
+            draw_pixel_text(&assets.font, name, row.x, top, &style);
+            let style = TextStyle {
+                align: Align::Right,
+                ..style
+            };
+            draw_pixel_text(&assets.font, &value, row.right(), top, &style);
+        }
+
+        let label = |action| {
+            (input.bindings.get(action).first())
+                .map(Binding::label)
+                .unwrap_or_default()
+        };
+        let close = label(Action::Pause);
+        let last = rows[rows.len() - 1];
+        let style = TextStyle {
+            scale: (scale * 0.75).max(1.0),
+            color: GRAY,
+            ..Default::default()
+        };
+        let hint = if self.waiting {
+            format!("{close}: cancel")
+        } else {
+            format!(
+                "{}: change   Left/Right: pick binding\n{}: reset   {close}: close",
+                label(Action::Confirm),
+                label(Action::ResetBinding)
+            )
+        };
+        draw_pixel_text(
+            &assets.font,
+            &hint,
+            last.x,
+            last.bottom() + font_size,
+            &style,
+        );
+    }
+}
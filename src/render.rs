@@ -0,0 +1,33 @@
+use std::cmp::Ordering;
+
+/// Something drawn in the y-sorted pass, pointing into the game's lists
+#[derive(Clone, Copy)]
+pub enum Drawable {
+    Player,
+    Enemy(usize),
+    Corpse(usize),
+    Pickup(usize),
+    Locker(usize),
+    Terminal(usize),
+    TileEntity((i16, i16)),
+}
+
+/// Drawables sorted by the y of their base, so lower ones are drawn in front
+pub struct RenderQueue {
+    items: Vec<(f32, Drawable)>,
+}
+impl RenderQueue {
+    pub fn new() -> Self {
+        Self { items: Vec::new() }
+    }
+    /// Queues `drawable`, whose feet are at `base_y`
+    pub fn push(&mut self, base_y: f32, drawable: Drawable) {
+        self.items.push((base_y, drawable));
+    }
+    /// Empties the queue, back to front. Ties keep the order they were pushed in.
+    pub fn drain(&mut self) -> impl Iterator<Item = Drawable> + '_ {
+        self.items
+            .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        self.items.drain(..).map(|(_, drawable)| drawable)
+    }
+}
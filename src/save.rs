@@ -0,0 +1,453 @@
+use std::{
+    io::{Error, ErrorKind},
+    path::PathBuf,
+    str::{FromStr, SplitWhitespace},
+};
+
+use macroquad::prelude::*;
+
+use crate::{
+    assets::{TileEntity, World},
+    enemy::{ENEMIES, Enemy},
+    modifier::{Element, MODIFIERS, Modifier, WeaponInstance},
+    pickup::{LockerItem, Pickup, PickupKind},
+    player::{PROJECTILES, Player, Projectile, WEAPONS},
+    utils::*,
+};
+
+pub const SAVE_SLOTS: usize = 3;
+/// Bumped whenever the format changes. Saves of other versions are refused.
+const SAVE_VERSION: u32 = 1;
+
+/// Everything about a game that changes while playing. The rest is rebuilt from the map.
+pub struct GameState {
+    pub player: Player,
+    pub world: World,
+    pub enemies: Vec<Enemy>,
+    pub projectiles: Vec<Projectile>,
+    pub pickups: Vec<Pickup>,
+}
+impl GameState {
+    pub fn new() -> Self {
+        let world = World::default();
+        let mut player = Player::new();
+        player.pos = world.get_interactable_spawn(16).unwrap();
+        Self {
+            player,
+            pickups: world.get_pickup_spawns(),
+            world,
+            enemies: Vec::new(),
+            projectiles: Vec::new(),
+        }
+    }
+    /// Parses a save written by [`serialize`], on top of a freshly loaded map
+    fn parse(text: &str) -> std::io::Result<Self> {
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+        let mut entries = config_entries(text);
+        match entries.next() {
+            Some(("version", version)) if version.parse() == Ok(SAVE_VERSION) => {}
+            Some(("version", version)) => {
+                return Err(invalid(format!("unsupported save version {version}")));
+            }
+            _ => return Err(invalid("save has no version".to_string())),
+        }
+        let mut state = Self::new();
+        state.pickups.clear();
+        state.world.tile_entities.clear();
+        for (key, value) in entries {
+            let mut fields = Fields(value.split_whitespace());
+            state
+                .load_entry(key, &mut fields)
+                .ok_or_else(|| invalid(format!("invalid {key} entry")))?;
+        }
+        Ok(state)
+    }
+    fn load_entry(&mut self, key: &str, fields: &mut Fields) -> Option<()> {
+        match key {
+            "player" => {
+                let player = &mut self.player;
+                player.pos = fields.vec2()?;
+                player.velocity = fields.vec2()?;
+                player.health = fields.next()?;
+                player.armor = fields.next()?;
+                player.ammo = fields.next()?;
+                player.credits = fields.next()?;
+            }
+            "weapon" => self.player.weapon = Some(fields.weapon()?),
+            "spawned" => {
+                let spawner = (fields.next()?, fields.next()?);
+                self.player.spawned_spawners.push(spawner);
+            }
+            "locker" => {
+                let locker = self.world.lockers.get_mut(fields.next::<usize>()?)?;
+                locker.item = match fields.word()? {
+                    "empty" => None,
+                    "weapon" => Some(LockerItem::Weapon(fields.weapon()?)),
+                    "modifier" => Some(LockerItem::Modifier(fields.modifier()?)),
+                    _ => return None,
+                };
+            }
+            "tile_entity" => {
+                let (x, y, tile) = (fields.next()?, fields.next()?, fields.next()?);
+                let mut entity = TileEntity::from_tile(tile, (x, y))?;
+                entity.enabled = fields.next()?;
+                self.world.tile_entities.insert((x, y), entity);
+            }
+            "enemy" => {
+                let ty = ENEMIES.get(fields.next::<usize>()?)?;
+                let mut enemy = Enemy::new(ty, fields.vec2()?);
+                enemy.health = fields.next()?;
+                enemy.animation_time = fields.next()?;
+                enemy.direction = fields.vec2()?;
+                enemy.velocity = fields.vec2()?;
+                enemy.knockback = fields.vec2()?;
+                enemy.emerging = fields.next()?;
+                enemy.state = fields.next()?;
+                enemy.spawner = match fields.word()? {
+                    "none" => None,
+                    x => Some((x.parse().ok()?, fields.next()?)),
+                };
+                enemy.status = match fields.element()? {
+                    Some(element) => Some((element, fields.next()?)),
+                    None => None,
+                };
+                self.enemies.push(enemy);
+            }
+            "projectile" => {
+                let ty = PROJECTILES.get(fields.next::<usize>()?)?;
+                let mut projectile =
+                    Projectile::new(ty, fields.vec2()?, fields.vec2()?, fields.next()?);
+                projectile.time = fields.next()?;
+                projectile.scale = fields.next()?;
+                projectile.damage_multiplier = fields.next()?;
+                projectile.pierce = fields.next()?;
+                projectile.element = fields.element()?;
+                self.projectiles.push(projectile);
+            }
+            "pickup" => {
+                let (pos, velocity, time) = (fields.vec2()?, fields.vec2()?, fields.next()?);
+                let kind = match fields.word()? {
+                    "health" => PickupKind::Health(fields.next()?),
+                    "armor" => PickupKind::Armor(fields.next()?),
+                    "ammo" => PickupKind::Ammo(fields.next()?),
+                    "credits" => PickupKind::Credits(fields.next()?),
+                    "weapon" => PickupKind::Weapon(fields.weapon()?),
+                    "modifier" => PickupKind::Modifier(fields.modifier()?),
+                    _ => return None,
+                };
+                let mut pickup = Pickup::new(kind, pos);
+                pickup.velocity = velocity;
+                pickup.time = time;
+                self.pickups.push(pickup);
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Space separated values of a save entry, read in order
+struct Fields<'a>(SplitWhitespace<'a>);
+impl<'a> Fields<'a> {
+    fn word(&mut self) -> Option<&'a str> {
+        self.0.next()
+    }
+    fn next<T: FromStr>(&mut self) -> Option<T> {
+        self.word()?.parse().ok()
+    }
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(vec2(self.next()?, self.next()?))
+    }
+    fn modifier(&mut self) -> Option<Modifier> {
+        MODIFIERS.get(self.next::<usize>()?).copied()
+    }
+    /// A weapon followed by its modifiers, taking up the rest of the entry
+    fn weapon(&mut self) -> Option<WeaponInstance> {
+        let weapon = WEAPONS.get(self.next::<usize>()?)?;
+        let mut modifiers = Vec::new();
+        while self.0.clone().next().is_some() {
+            modifiers.push(self.modifier()?);
+        }
+        Some(WeaponInstance { weapon, modifiers })
+    }
+    fn element(&mut self) -> Option<Option<Element>> {
+        match self.word()? {
+            "none" => Some(None),
+            "burn" => Some(Some(Element::Burn)),
+            "cryo" => Some(Some(Element::Cryo)),
+            _ => None,
+        }
+    }
+}
+
+/// Index of `item` in `list`, comparing addresses since different statics can hold the same values
+fn index_of<T>(list: &[&T], item: &T) -> usize {
+    list.iter().position(|f| std::ptr::eq(*f, item)).unwrap()
+}
+fn element_name(element: Option<Element>) -> &'static str {
+    match element {
+        None => "none",
+        Some(Element::Burn) => "burn",
+        Some(Element::Cryo) => "cryo",
+    }
+}
+fn modifier_index(modifier: Modifier) -> usize {
+    MODIFIERS.iter().position(|f| *f == modifier).unwrap()
+}
+fn weapon_fields(instance: &WeaponInstance) -> String {
+    let mut fields = index_of(WEAPONS, instance.weapon).to_string();
+    for modifier in &instance.modifiers {
+        fields += &format!(" {}", modifier_index(*modifier));
+    }
+    fields
+}
+
+/// Writes the parts of a game that change while playing as `key = values` lines
+pub fn serialize(
+    player: &Player,
+    world: &World,
+    enemies: &[Enemy],
+    projectiles: &[Projectile],
+    pickups: &[Pickup],
+) -> String {
+    let mut text = format!("# space splatter save\nversion = {SAVE_VERSION}\n");
+    let mut entry = |key: &str, values: String| text += &format!("{key} = {values}\n");
+
+    entry(
+        "player",
+        format!(
+            "{} {} {} {} {} {} {} {}",
+            player.pos.x,
+            player.pos.y,
+            player.velocity.x,
+            player.velocity.y,
+            player.health,
+            player.armor,
+            player.ammo,
+            player.credits
+        ),
+    );
+    if let Some(instance) = &player.weapon {
+        entry("weapon", weapon_fields(instance));
+    }
+    for (x, y) in &player.spawned_spawners {
+        entry("spawned", format!("{x} {y}"));
+    }
+
+    for (i, locker) in world.lockers.iter().enumerate() {
+        let item = match &locker.item {
+            None => "empty".to_string(),
+            Some(LockerItem::Weapon(instance)) => format!("weapon {}", weapon_fields(instance)),
+            Some(LockerItem::Modifier(modifier)) => {
+                format!("modifier {}", modifier_index(*modifier))
+            }
+        };
+        entry("locker", format!("{i} {item}"));
+    }
+    for ((x, y), entity) in &world.tile_entities {
+        entry(
+            "tile_entity",
+            format!("{x} {y} {} {}", entity.tile_index, entity.enabled),
+        );
+    }
+
+    for enemy in enemies {
+        let status = match enemy.status {
+            Some((element, time)) => format!("{} {time}", element_name(Some(element))),
+            None => "none".to_string(),
+        };
+        let spawner = match enemy.spawner {
+            Some((x, y)) => format!("{x} {y}"),
+            None => "none".to_string(),
+        };
+        let ty = ENEMIES
+            .iter()
+            .position(|f| std::ptr::eq(f, enemy.ty))
+            .unwrap();
+        entry(
+            "enemy",
+            format!(
+                "{ty} {} {} {} {} {} {} {} {} {} {} {} {} {spawner} {status}",
+                enemy.pos.x,
+                enemy.pos.y,
+                enemy.health,
+                enemy.animation_time,
+                enemy.direction.x,
+                enemy.direction.y,
+                enemy.velocity.x,
+                enemy.velocity.y,
+                enemy.knockback.x,
+                enemy.knockback.y,
+                enemy.emerging,
+                enemy.state
+            ),
+        );
+    }
+    for projectile in projectiles {
+        entry(
+            "projectile",
+            format!(
+                "{} {} {} {} {} {} {} {} {} {} {}",
+                index_of(PROJECTILES, projectile.ty),
+                projectile.pos.x,
+                projectile.pos.y,
+                projectile.dir.x,
+                projectile.dir.y,
+                projectile.friendly,
+                projectile.time,
+                projectile.scale,
+                projectile.damage_multiplier,
+                projectile.pierce,
+                element_name(projectile.element)
+            ),
+        );
+    }
+    for pickup in pickups {
+        let kind = match &pickup.kind {
+            PickupKind::Health(amount) => format!("health {amount}"),
+            PickupKind::Armor(amount) => format!("armor {amount}"),
+            PickupKind::Ammo(amount) => format!("ammo {amount}"),
+            PickupKind::Credits(amount) => format!("credits {amount}"),
+            PickupKind::Weapon(instance) => format!("weapon {}", weapon_fields(instance)),
+            PickupKind::Modifier(modifier) => format!("modifier {}", modifier_index(*modifier)),
+        };
+        entry(
+            "pickup",
+            format!(
+                "{} {} {} {} {} {kind}",
+                pickup.pos.x, pickup.pos.y, pickup.velocity.x, pickup.velocity.y, pickup.time
+            ),
+        );
+    }
+    text
+}
+
+fn slot_path(slot: usize) -> PathBuf {
+    config_dir()
+        .join("saves")
+        .join(format!("slot{}.sav", slot + 1))
+}
+pub fn slot_exists(slot: usize) -> bool {
+    slot_path(slot).exists()
+}
+pub fn save_slot(slot: usize, text: &str) -> std::io::Result<()> {
+    let path = slot_path(slot);
+    std::fs::create_dir_all(path.parent().unwrap())?;
+    std::fs::write(path, text)
+}
+pub fn load_slot(slot: usize) -> std::io::Result<GameState> {
+    GameState::parse(&std::fs::read_to_string(slot_path(slot))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut state = GameState::new();
+        let player = &mut state.player;
+        player.pos = vec2(120.5, -33.25);
+        player.velocity = vec2(1.5, -2.0);
+        player.health = 42.0;
+        player.armor = 12.5;
+        player.ammo = 77;
+        player.credits = 130;
+        player.weapon = Some(WeaponInstance {
+            weapon: WEAPONS[2],
+            modifiers: vec![MODIFIERS[0], MODIFIERS[3]],
+        });
+        player.spawned_spawners = vec![(3, 4), (-7, 12)];
+
+        if let Some(locker) = state.world.lockers.first_mut() {
+            locker.item = None;
+        }
+        for entity in state.world.tile_entities.values_mut() {
+            entity.enabled = !entity.enabled;
+        }
+
+        let mut enemy = Enemy::new(&ENEMIES[1], vec2(64.0, 80.0));
+        enemy.health = 3.5;
+        enemy.direction = vec2(0.0, -1.0);
+        enemy.emerging = false;
+        enemy.state = 1;
+        enemy.status = Some((Element::Cryo, 1.25));
+        enemy.spawner = Some((3, 4));
+        state.enemies.push(enemy);
+
+        let mut projectile = Projectile::new(PROJECTILES[1], vec2(10.0, 20.0), Vec2::X, true);
+        projectile.pierce = 2;
+        projectile.element = Some(Element::Burn);
+        state.projectiles.push(projectile);
+
+        state.pickups = vec![
+            Pickup::new(PickupKind::Credits(7), vec2(1.0, 2.0)),
+            Pickup::new(
+                PickupKind::Weapon(WeaponInstance {
+                    weapon: WEAPONS[4],
+                    modifiers: vec![MODIFIERS[1]],
+                }),
+                vec2(-5.0, 6.5),
+            ),
+        ];
+
+        let text = serialize(
+            &state.player,
+            &state.world,
+            &state.enemies,
+            &state.projectiles,
+            &state.pickups,
+        );
+        let loaded = GameState::parse(&text).unwrap();
+
+        let (player, original) = (&loaded.player, &state.player);
+        assert_eq!(player.pos, original.pos);
+        assert_eq!(player.velocity, original.velocity);
+        assert_eq!(player.health, original.health);
+        assert_eq!(player.armor, original.armor);
+        assert_eq!(player.ammo, original.ammo);
+        assert_eq!(player.credits, original.credits);
+        assert_eq!(player.weapon, original.weapon);
+        assert_eq!(player.spawned_spawners, original.spawned_spawners);
+
+        for (locker, original) in loaded.world.lockers.iter().zip(&state.world.lockers) {
+            assert_eq!(locker.item, original.item);
+        }
+        assert_eq!(
+            loaded.world.tile_entities.len(),
+            state.world.tile_entities.len()
+        );
+        for (pos, entity) in &state.world.tile_entities {
+            let loaded = &loaded.world.tile_entities[pos];
+            assert_eq!(loaded.tile_index, entity.tile_index);
+            assert_eq!(loaded.enabled, entity.enabled);
+        }
+
+        let (enemy, original) = (&loaded.enemies[0], &state.enemies[0]);
+        assert_eq!(loaded.enemies.len(), 1);
+        assert!(std::ptr::eq(enemy.ty, original.ty));
+        assert_eq!(enemy.pos, original.pos);
+        assert_eq!(enemy.health, original.health);
+        assert_eq!(enemy.direction, original.direction);
+        assert_eq!(enemy.emerging, original.emerging);
+        assert_eq!(enemy.state, original.state);
+        assert_eq!(enemy.status, original.status);
+        assert_eq!(enemy.spawner, original.spawner);
+
+        let (projectile, original) = (&loaded.projectiles[0], &state.projectiles[0]);
+        assert_eq!(loaded.projectiles.len(), 1);
+        assert!(std::ptr::eq(projectile.ty, original.ty));
+        assert_eq!(projectile.pos, original.pos);
+        assert_eq!(projectile.dir, original.dir);
+        assert_eq!(projectile.friendly, original.friendly);
+        assert_eq!(projectile.pierce, original.pierce);
+        assert_eq!(projectile.element, original.element);
+
+        assert_eq!(loaded.pickups.len(), state.pickups.len());
+        for (pickup, original) in loaded.pickups.iter().zip(&state.pickups) {
+            assert_eq!(pickup.kind, original.kind);
+            assert_eq!(pickup.pos, original.pos);
+            assert_eq!(pickup.velocity, original.velocity);
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{
+    menu::Menu,
+    rebind::RebindMenu,
+    save::{SAVE_SLOTS, slot_exists},
+};
+
+/// A screen on the scene stack
+pub enum Scene {
+    Title(Menu),
+    Playing,
+    /// Gameplay is frozen while paused
+    Paused(Menu),
+    Settings(RebindMenu),
+    GameOver(Menu),
+    /// Save slots to write the game to, followed by a back option
+    SaveSlots(Menu),
+    /// Save slots to load a game from, followed by a back option
+    LoadSlots(Menu),
+}
+impl Scene {
+    pub fn title() -> Self {
+        Scene::Title(Menu::new(
+            "Space Splatter",
+            &["New game", "Load game", "Settings", "Quit"],
+        ))
+    }
+    pub fn paused() -> Self {
+        Scene::Paused(Menu::new(
+            "Paused",
+            &[
+                "Resume",
+                "Save game",
+                "Load game",
+                "Settings",
+                "Quit to title",
+            ],
+        ))
+    }
+    pub fn game_over() -> Self {
+        Scene::GameOver(Menu::new(
+            "Game over",
+            &["Retry", "Load game", "Quit to title"],
+        ))
+    }
+    pub fn save_slots() -> Self {
+        Scene::SaveSlots(Menu::new("Save game", &slot_options()))
+    }
+    pub fn load_slots() -> Self {
+        Scene::LoadSlots(Menu::new("Load game", &slot_options()))
+    }
+}
+fn slot_options() -> Vec<String> {
+    (0..SAVE_SLOTS)
+        .map(|slot| {
+            if slot_exists(slot) {
+                format!("Slot {}", slot + 1)
+            } else {
+                format!("Slot {} (empty)", slot + 1)
+            }
+        })
+        .chain([String::from("Back")])
+        .collect()
+}
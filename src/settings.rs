@@ -0,0 +1,130 @@
+use macroquad::{
+    prelude::*,
+    window::{request_new_screen_size, set_fullscreen},
+};
+
+use crate::utils::*;
+
+const SETTINGS_FILE: &str = "settings.cfg";
+/// UI scales the settings menu cycles through
+pub static UI_SCALES: &[f32] = &[0.5, 0.75, 1.0, 1.5, 2.0];
+/// Volumes the settings menu cycles through
+pub static VOLUMES: &[f32] = &[0.0, 0.25, 0.5, 0.75, 1.0];
+/// Window sizes the settings menu cycles through, all multiples of the pixel camera
+pub static WINDOW_SIZES: &[(u32, u32)] = &[(1024, 576), (1536, 864), (2048, 1152), (2560, 1440)];
+
+/// Player preferences, saved next to the key bindings
+#[derive(Clone)]
+pub struct Settings {
+    /// Show damage dealt to enemies as floating numbers
+    pub damage_numbers: bool,
+    /// Multiplies the size of the HUD, which is still rounded to whole pixels
+    pub ui_scale: f32,
+    /// Scales both the sound effects and music
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    /// Size of the window when not fullscreen, either picked from `WINDOW_SIZES` or resized to
+    pub window_size: (u32, u32),
+    pub fullscreen: bool,
+}
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            damage_numbers: true,
+            ui_scale: 1.0,
+            master_volume: 1.0,
+            sfx_volume: 1.0,
+            music_volume: 0.75,
+            window_size: WINDOW_SIZES[0],
+            fullscreen: false,
+        }
+    }
+}
+impl Settings {
+    /// Parses `key = value` lines. Missing or invalid values keep their defaults.
+    fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+        for (key, value) in config_entries(text) {
+            match key {
+                "damage_numbers" => {
+                    settings.damage_numbers = value.parse().unwrap_or(settings.damage_numbers);
+                }
+                "ui_scale" => {
+                    settings.ui_scale = value
+                        .parse()
+                        .ok()
+                        .filter(|scale| UI_SCALES.contains(scale))
+                        .unwrap_or(settings.ui_scale);
+                }
+                "master_volume" | "sfx_volume" | "music_volume" => {
+                    let volume = match key {
+                        "master_volume" => &mut settings.master_volume,
+                        "sfx_volume" => &mut settings.sfx_volume,
+                        _ => &mut settings.music_volume,
+                    };
+                    *volume = value
+                        .parse()
+                        .ok()
+                        .filter(|volume| VOLUMES.contains(volume))
+                        .unwrap_or(*volume);
+                }
+                "window_size" => {
+                    settings.window_size = value
+                        .split_once('x')
+                        .and_then(|(width, height)| {
+                            Some((width.parse().ok()?, height.parse().ok()?))
+                        })
+                        .filter(|(width, height)| *width > 0 && *height > 0)
+                        .unwrap_or(settings.window_size);
+                }
+                "fullscreen" => {
+                    settings.fullscreen = value.parse().unwrap_or(settings.fullscreen);
+                }
+                _ => {}
+            }
+        }
+        settings
+    }
+    fn serialize(&self) -> String {
+        format!(
+            "damage_numbers = {}\nui_scale = {}\nmaster_volume = {}\nsfx_volume = {}\nmusic_volume = {}\nwindow_size = {}x{}\nfullscreen = {}\n",
+            self.damage_numbers,
+            self.ui_scale,
+            self.master_volume,
+            self.sfx_volume,
+            self.music_volume,
+            self.window_size.0,
+            self.window_size.1,
+            self.fullscreen
+        )
+    }
+    /// Window configuration for macroquad, so the window opens as it was last set
+    pub fn window_conf(&self) -> Conf {
+        Conf {
+            window_title: String::from("space splatter"),
+            window_width: self.window_size.0 as i32,
+            window_height: self.window_size.1 as i32,
+            fullscreen: self.fullscreen,
+            ..Default::default()
+        }
+    }
+    /// Applies the window settings to the open window
+    pub fn apply_window(&self) {
+        set_fullscreen(self.fullscreen);
+        if !self.fullscreen {
+            request_new_screen_size(self.window_size.0 as f32, self.window_size.1 as f32);
+        }
+    }
+    /// Loads settings from the config directory, falling back to the defaults
+    pub fn load() -> Self {
+        std::fs::read_to_string(config_dir().join(SETTINGS_FILE))
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+    pub fn save(&self) -> std::io::Result<()> {
+        let dir = config_dir();
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join(SETTINGS_FILE), self.serialize())
+    }
+}
@@ -0,0 +1,76 @@
+use macroquad::prelude::*;
+
+use crate::{
+    assets::Assets,
+    audio::Sfx,
+    interact::{Interactable, Prompt},
+    player::Player,
+};
+
+const TERMINAL_REACH: f32 = 22.0;
+
+/// Logs read from terminals, indexed by tile id from 144 in the interactable layer
+static TERMINAL_LOGS: &[&[&str]] = &[
+    &[
+        "Crew log: hangar sealed after the breach",
+        "Barriers only drop once a wing is clear",
+        "Whatever got in, it breeds in the vents",
+    ],
+    &[
+        "Maintenance: lower hall lights failing",
+        "Keep the hall doors shut at night",
+        "Reactor is stable. Nobody is left to check",
+    ],
+];
+
+/// Console that shows a log one line at a time
+pub struct Terminal {
+    pub pos: Vec2,
+    log: &'static [&'static str],
+    /// Line being shown, or `None` before the log is opened
+    line: Option<usize>,
+}
+impl Terminal {
+    /// Gets the terminal placed by a tile in the interactable layer
+    pub fn from_tile(tile: i16, pos: Vec2) -> Option<Self> {
+        let log = TERMINAL_LOGS.get(usize::try_from(tile - 144).ok()?)?;
+        Some(Self {
+            pos,
+            log,
+            line: None,
+        })
+    }
+    fn center(&self) -> Vec2 {
+        self.pos + 8.0
+    }
+    /// Closes the log once the player walks away
+    pub fn update(&mut self, player: &Player) {
+        if (player.pos + 8.0).distance(self.center()) >= TERMINAL_REACH {
+            self.line = None;
+        }
+    }
+    pub fn draw(&self, assets: &Assets) {
+        assets
+            .tileset
+            .draw_tile(self.pos.x, self.pos.y, 0.0, 9.0, None);
+    }
+}
+impl Interactable for Terminal {
+    fn prompt(&self, _player: &Player) -> Option<Prompt> {
+        let text = match self.line {
+            None => "Read terminal".to_string(),
+            Some(line) => format!("{} ({}/{})", self.log[line], line + 1, self.log.len()),
+        };
+        Some(Prompt {
+            text,
+            pos: self.center(),
+            radius: TERMINAL_REACH,
+            priority: 0,
+            hold: None,
+        })
+    }
+    fn interact(&mut self, _player: &mut Player, _sfx: &mut Vec<(Sfx, Vec2)>) {
+        let next = self.line.map_or(0, |line| line + 1);
+        self.line = (next < self.log.len()).then_some(next);
+    }
+}
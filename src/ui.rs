@@ -1,63 +1,159 @@
 use crate::assets::Assets;
+use crate::enemy::Enemy;
+use crate::font::{TextStyle, draw_pixel_text};
+use crate::hud::{Anchor, HudLayout};
+use crate::interact::Prompt;
+use crate::menu::MENU_ACCENT;
+use crate::modifier::draw_modifier_row;
+use crate::pickup::{AMMO_COLOR, ARMOR_COLOR, CREDITS_COLOR, MAX_AMMO, MAX_ARMOR};
 use crate::player::Player;
-use crate::utils::*;
-use macroquad::miniquad::window::screen_size;
 use macroquad::prelude::*;
 
 pub const PLAYER_HEALTH_COLOR: Color = Color::from_hex(0x87d1ef);
+const MAX_SHOWN_CREDITS: u32 = 40;
 
-pub fn draw_ui(assets: &Assets, show_tooltip: bool, player: &Player) {
-    let (actual_screen_width, actual_screen_height) = screen_size();
-    let scale_factor = (actual_screen_width / SCREEN_WIDTH)
-        .min(actual_screen_height / SCREEN_HEIGHT)
-        .floor()
-        .max(1.0);
+/// Width of the health, armor and ammo bars, in HUD pixels
+const BAR_WIDTH: f32 = 170.0;
+/// Where the bars start inside the health bar frame
+const BAR_INSET: f32 = 8.0;
+const BOSS_HEALTH_COLOR: Color = Color::from_hex(0xd9434b);
+const BOSS_BAR_WIDTH: f32 = 200.0;
 
-    let x = 10.0 * scale_factor;
-    let y = 10.0 * scale_factor;
-    draw_rectangle(
-        x + 8.0 * scale_factor,
-        y + 2.0 * scale_factor,
-        170.0 * scale_factor * player.health / 100.0,
-        20.0 * scale_factor,
-        BLACK,
-    );
-    draw_rectangle(
-        x + 8.0 * scale_factor,
-        y + 2.0 * scale_factor,
-        170.0 * scale_factor * player.health / 100.0,
-        20.0 * scale_factor,
-        PLAYER_HEALTH_COLOR,
-    );
+pub fn draw_ui(
+    assets: &Assets,
+    layout: &mut HudLayout,
+    prompt: Option<(&Prompt, f32)>,
+    interact_label: &str,
+    player: &Player,
+    enemies: &[Enemy],
+) {
+    let scale = layout.scale;
+    // element space to window space
+    let rect = |origin: Vec2, x: f32, y: f32, w: f32, h: f32, color: Color| {
+        draw_rectangle(
+            origin.x + x * scale,
+            origin.y + y * scale,
+            w * scale,
+            h * scale,
+            color,
+        );
+    };
+
+    let frame_size = assets.healthbar.size();
+    let pos = layout.place(Anchor::TopLeft, frame_size);
+    let health_width = BAR_WIDTH * player.health.max(0.0) / 100.0;
+    rect(pos, BAR_INSET, 2.0, health_width, 20.0, PLAYER_HEALTH_COLOR);
+    if player.armor > 0.0 {
+        let armor_width = BAR_WIDTH * player.armor / MAX_ARMOR;
+        rect(pos, BAR_INSET, 18.0, armor_width, 4.0, ARMOR_COLOR);
+    }
     draw_texture_ex(
         &assets.healthbar,
-        x,
-        y,
+        pos.x,
+        pos.y,
         WHITE,
         DrawTextureParams {
-            dest_size: Some(vec2(
-                assets.tooltip.width() * scale_factor,
-                assets.tooltip.height() * scale_factor,
-            )),
+            dest_size: Some(frame_size * scale),
             ..Default::default()
         },
     );
 
-    if show_tooltip {
-        let x = (actual_screen_width - assets.tooltip.width() * scale_factor) / 2.0;
-        let y = actual_screen_height - assets.tooltip.height() * scale_factor - 4.0 * scale_factor;
-        draw_texture_ex(
-            &assets.tooltip,
-            x,
-            y,
+    if player
+        .weapon
+        .as_ref()
+        .is_some_and(|instance| instance.weapon.ammo_cost > 0)
+    {
+        let style = TextStyle {
+            scale,
+            color: AMMO_COLOR,
+            outline: Some(BLACK),
+            ..Default::default()
+        };
+        let count = format!("{}/{MAX_AMMO}", player.ammo);
+        let count_width = assets.font.measure(&count, &style).x / scale;
+        let pos = layout.place(
+            Anchor::TopLeft,
+            vec2(BAR_INSET + BAR_WIDTH + 5.0 + count_width, 5.0),
+        );
+        rect(pos, BAR_INSET - 1.0, 0.0, BAR_WIDTH + 2.0, 5.0, BLACK);
+        let ammo_width = BAR_WIDTH * player.ammo as f32 / MAX_AMMO as f32;
+        rect(pos, BAR_INSET, 1.0, ammo_width, 3.0, AMMO_COLOR);
+        let count_x = pos.x + (BAR_INSET + BAR_WIDTH + 5.0) * scale;
+        draw_pixel_text(&assets.font, &count, count_x, pos.y, &style);
+    }
+    if let Some(instance) = &player.weapon
+        && !instance.modifiers.is_empty()
+    {
+        let width = instance.modifiers.len() as f32 * 8.0;
+        let pos = layout.place(Anchor::TopLeft, vec2(BAR_INSET + width, 7.0));
+        draw_modifier_row(&instance.modifiers, pos.x + BAR_INSET * scale, pos.y, scale);
+    }
+    let shown_credits = player.credits.min(MAX_SHOWN_CREDITS);
+    if shown_credits > 0 {
+        let pos = layout.place(
+            Anchor::TopLeft,
+            vec2(BAR_INSET + shown_credits as f32 * 4.0, 3.0),
+        );
+        for i in 0..shown_credits {
+            rect(
+                pos,
+                BAR_INSET + i as f32 * 4.0,
+                0.0,
+                3.0,
+                3.0,
+                CREDITS_COLOR,
+            );
+        }
+    }
+
+    // bosses are the enemies with their own music
+    if let Some(boss) = (enemies.iter()).find(|enemy| !enemy.emerging && enemy.ty.music.is_some()) {
+        let pos = layout.place(Anchor::TopCenter, vec2(BOSS_BAR_WIDTH + 2.0, 6.0));
+        rect(pos, 0.0, 0.0, BOSS_BAR_WIDTH + 2.0, 6.0, BLACK);
+        let health_width = BOSS_BAR_WIDTH * (boss.health / boss.ty.health).clamp(0.0, 1.0);
+        rect(pos, 1.0, 1.0, health_width.ceil(), 4.0, BOSS_HEALTH_COLOR);
+    }
+
+    if let Some((prompt, progress)) = prompt {
+        draw_prompt(assets, layout, prompt, progress, interact_label);
+    }
+}
+
+/// Draws the interaction prompt at the bottom center, styled after `tooltip.ase`
+fn draw_prompt(
+    assets: &Assets,
+    layout: &mut HudLayout,
+    prompt: &Prompt,
+    progress: f32,
+    interact_label: &str,
+) {
+    let scale = layout.scale;
+    let style = TextStyle {
+        scale: scale * 2.0,
+        ..Default::default()
+    };
+    let text = format!("{interact_label}: {}", prompt.text);
+    let padding = 4.0;
+    let size = assets.font.measure(&text, &style) / scale + padding * 2.0;
+    let pos = layout.place(Anchor::BottomCenter, size);
+    let size = size * scale;
+    draw_rectangle(pos.x, pos.y, size.x, size.y, WHITE);
+    draw_rectangle(
+        pos.x + scale,
+        pos.y + scale,
+        size.x - scale * 2.0,
+        size.y - scale * 2.0,
+        MENU_ACCENT,
+    );
+    if progress > 0.0 {
+        draw_rectangle(
+            pos.x + scale,
+            pos.y + size.y - scale * 3.0,
+            ((size.x - scale * 2.0) * progress).floor(),
+            scale * 2.0,
             WHITE,
-            DrawTextureParams {
-                dest_size: Some(vec2(
-                    assets.tooltip.width() * scale_factor,
-                    assets.tooltip.height() * scale_factor,
-                )),
-                ..Default::default()
-            },
         );
     }
+    let text_pos = pos + padding * scale;
+    draw_pixel_text(&assets.font, &text, text_pos.x, text_pos.y, &style);
 }
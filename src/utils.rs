@@ -1,4 +1,6 @@
-use macroquad::prelude::*;
+use std::path::PathBuf;
+
+use macroquad::{miniquad::window::screen_size, prelude::*};
 
 pub const SCREEN_WIDTH: f32 = 256.0 * 2.0;
 pub const SCREEN_HEIGHT: f32 = 144.0 * 2.0;
@@ -13,19 +15,52 @@ pub fn create_camera(w: f32, h: f32) -> Camera2D {
         ..Default::default()
     }
 }
-pub fn get_input_axis() -> Vec2 {
-    let mut i = Vec2::ZERO;
-    if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-        i.x -= 1.0;
-    }
-    if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-        i.x += 1.0;
+/// Where to draw something that moved from `last` to `pos` in the last simulation step,
+/// `alpha` of the way into the next one, so motion stays smooth between fixed steps
+pub fn interpolate(last: Vec2, pos: Vec2, alpha: f32) -> Vec2 {
+    last.lerp(pos, alpha)
+}
+/// Placement of the upscaled pixel camera in the window.
+/// The aspect ratio is kept, leaving bars on the sides that don't fit.
+pub struct Letterbox {
+    pub scale: f32,
+    /// Top left corner of the pixel camera in the window
+    pub offset: Vec2,
+}
+impl Letterbox {
+    pub fn get() -> Self {
+        let (width, height) = screen_size();
+        let scale = (width / SCREEN_WIDTH).min(height / SCREEN_HEIGHT);
+        let offset = (vec2(width, height) - vec2(SCREEN_WIDTH, SCREEN_HEIGHT) * scale) / 2.0;
+        Self { scale, offset }
     }
-    if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
-        i.y -= 1.0;
+    /// Converts a window position to pixel camera space
+    pub fn screen_to_pixel(&self, pos: Vec2) -> Vec2 {
+        (pos - self.offset) / self.scale
     }
-    if is_key_down(KeyCode::S) || is_key_down(KeyCode::Down) {
-        i.y += 1.0;
+    /// Converts a window position to world space, as seen through the pixel camera
+    pub fn screen_to_world(&self, pos: Vec2, camera: &Camera2D) -> Vec2 {
+        self.screen_to_pixel(pos) + camera.target - vec2(SCREEN_WIDTH, SCREEN_HEIGHT) / 2.0
     }
-    i
+}
+/// Directory for config files, following each platform's convention
+pub fn config_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.unwrap_or_default().join("space-splatter")
+}
+/// Splits config text into `key = value` pairs, skipping blank lines and `#` comments
+pub fn config_entries(text: &str) -> impl Iterator<Item = (&str, &str)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
 }